@@ -0,0 +1,143 @@
+use std::{fmt, fs};
+
+use crate::{lint::Linter, question::Parser};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{} {}: {}", self.path, self.line, self.column, self.severity, self.message)
+    }
+}
+
+impl Diagnostic {
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "path": self.path,
+            "line": self.line,
+            "column": self.column,
+            "severity": self.severity.to_string(),
+            "message": self.message,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Human,
+    Json,
+}
+
+/// Parses every question in `src` and collects all the errors and warnings, instead of stopping at the first one.
+pub fn check_source(src: &str, path: &str, linter: &mut Linter) -> Vec<Diagnostic> {
+    let mut parser = Parser::new(src, path);
+    let mut diagnostics = Vec::new();
+
+    while let Some(res) = parser.next_located() {
+        match res {
+            Err(e) => diagnostics.push(Diagnostic {
+                path: path.to_string(),
+                line: e.line,
+                column: e.column,
+                severity: Severity::Error,
+                message: e.message,
+            }),
+            Ok(q) => {
+                let line = parser.line_number();
+                for message in linter.lint(&q, path, line) {
+                    diagnostics.push(Diagnostic {
+                        path: path.to_string(),
+                        line,
+                        column: 1,
+                        severity: Severity::Warning,
+                        message,
+                    });
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Entry point for `quickstudy check [--format human|json] <files>`.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut format = Format::Human;
+    let mut paths = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => format = Format::Json,
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("human") => Format::Human,
+                    Some("json") => Format::Json,
+                    Some(other) => return Err(format!("Unknown output format `{other}`!")),
+                    None => return Err(String::from("Expected an output format after `--format`!")),
+                }
+            }
+            _ => paths.push(arg),
+        }
+    }
+
+    if paths.is_empty() {
+        return Err(String::from("No files to check!"));
+    }
+
+    let mut diagnostics = Vec::new();
+    let mut linter = Linter::new();
+
+    for path in paths {
+        let src = fs::read_to_string(path).map_err(|e| format!("Could not read file `{path}`: {e}!"))?;
+        diagnostics.extend(check_source(&src, path, &mut linter));
+    }
+
+    match format {
+        Format::Human => {
+            for diagnostic in &diagnostics {
+                println!("{diagnostic}");
+            }
+        }
+        Format::Json => {
+            let json = diagnostics.iter().map(Diagnostic::to_json).collect::<Vec<_>>();
+            println!("{}", serde_json::Value::Array(json));
+        }
+    }
+
+    let errors = diagnostics.iter().filter(|d| d.severity == Severity::Error).count();
+    let warnings = diagnostics.len() - errors;
+
+    // Returning an error is what gives a non-zero exit status
+    if errors > 0 {
+        return Err(format!("Found {errors} error(s) and {warnings} warning(s)!"));
+    }
+
+    if format == Format::Human && warnings > 0 {
+        eprintln!("Found 0 error(s) and {warnings} warning(s).");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use std::fs;
+
+use crate::question::{Line, ParseError, Parser};
+
+/// Rewrites `src` in the canonical style, keeping comments and blank lines where they were.
+///
+/// Files with errors in them are left alone, since there's no way to know what they were meant to say.
+pub fn format_source(src: &str, path: &str) -> Result<String, Vec<ParseError>> {
+    let mut parser = Parser::new(src, path);
+    let mut formatted = String::new();
+    let mut errors = Vec::new();
+    // Files written on Windows keep their line endings
+    let newline = if src.contains("\r\n") { "\r\n" } else { "\n" };
+
+    while let Some(line) = parser.next_line() {
+        match line {
+            Line::Blank => {}
+            Line::Comment(comment) => formatted.push_str(comment.trim()),
+            Line::Question(Ok(q)) => formatted.push_str(&q.to_question().to_string()),
+            Line::Question(Err(e)) => errors.push(e),
+        }
+        formatted.push_str(newline);
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    // Blank lines at the end of the file don't mean anything.
+    let len = formatted.trim_end().len();
+    formatted.truncate(len);
+    if !formatted.is_empty() {
+        formatted.push_str(newline);
+    }
+
+    Ok(formatted)
+}
+
+/// Entry point for `quickstudy fmt [--check] <files>`.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut check = false;
+    let mut paths = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            _ => paths.push(arg),
+        }
+    }
+
+    if paths.is_empty() {
+        return Err(String::from("No files to format!"));
+    }
+
+    let mut broken = 0;
+    let mut unformatted = 0;
+
+    for path in paths {
+        let src = fs::read_to_string(path).map_err(|e| format!("Could not read file `{path}`: {e}!"))?;
+
+        match format_source(&src, path) {
+            Err(errors) => {
+                for e in errors {
+                    eprintln!("{path}:{}:{} {}", e.line, e.column, e.message);
+                }
+                broken += 1;
+            }
+            Ok(formatted) if formatted != src => {
+                if check {
+                    println!("{path}");
+                    unformatted += 1;
+                } else {
+                    fs::write(path, formatted).map_err(|e| format!("Could not write file `{path}`: {e}!"))?;
+                }
+            }
+            Ok(_) => {}
+        }
+    }
+
+    // Returning an error is what gives a non-zero exit status
+    if broken > 0 {
+        return Err(format!("Could not format {broken} file(s) with errors in them!"));
+    }
+    if unformatted > 0 {
+        return Err(format!("{unformatted} file(s) aren't formatted!"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,169 @@
+//! Turns what the learner does at a prompt into answers, the same way for every renderer.
+//!
+//! Renderers only show a `Prompt` and report the next `Event`, so moving between blanks, editing them and timing how
+//! long each one took all happen here, and a new renderer or kind of question doesn't have to deal with any of it.
+
+use std::time;
+
+use crate::{
+    render::{Cursor, Event, Prompt, QuestionView, Render, Response, Timing},
+    schedule::Rating,
+};
+
+/// Asks `question` until its answers are submitted, which is once the learner finishes the last blank. Gives back
+/// `None` if the learner stopped the session instead.
+pub fn ask<R: Render>(window: &mut R, question: &QuestionView) -> Result<Option<Response>, String> {
+    let asked_at = time::Instant::now();
+    let blanks = question.blanks().count();
+    // Without a blank there's no way to submit the question
+    if blanks == 0 {
+        return Err(String::from("Can't ask a question that has no blanks to fill in!"));
+    }
+
+    let mut answers = vec![String::new(); blanks];
+    // When each blank was first reached and first typed in, and how long has been spent in it
+    let mut reached = vec![None; blanks];
+    let mut first_key = vec![None; blanks];
+    let mut spent = vec![time::Duration::ZERO; blanks];
+    // Answers typed in all at once can't be told apart
+    let mut timed = true;
+
+    let mut cursor = Cursor { blank: 0, at: 0 };
+    let mut entered = time::Instant::now();
+
+    while cursor.blank < blanks {
+        let since = *reached[cursor.blank].get_or_insert(entered);
+        window.show_prompt(&Prompt::Blanks { question, answers: &answers, cursor });
+
+        let answer = &mut answers[cursor.blank];
+        let len = answer.chars().count();
+        let byte = |at: usize| answer.char_indices().nth(at).map_or(answer.len(), |(idx, _)| idx);
+
+        let previous = match cursor.blank {
+            0 => Cursor { at: 0, ..cursor },
+            blank => Cursor { blank: blank - 1, at: usize::MAX },
+        };
+        // Only finishing the last blank submits the question, so moving forward stops there
+        let following = Cursor { blank: (cursor.blank + 1).min(blanks - 1), at: usize::MAX };
+
+        let mut next = match window.next_event()? {
+            Event::Char(ch) => {
+                first_key[cursor.blank].get_or_insert_with(|| since.elapsed());
+                answer.insert(byte(cursor.at), ch);
+                Cursor { at: cursor.at + 1, ..cursor }
+            }
+            Event::Enter => Cursor { blank: cursor.blank + 1, at: usize::MAX },
+            Event::Line(line) => {
+                *answer = line;
+                Cursor { blank: cursor.blank + 1, at: usize::MAX }
+            }
+            Event::Answers(given) => {
+                for (answer, given) in answers[cursor.blank..].iter_mut().zip(given) {
+                    *answer = given;
+                }
+                timed = false;
+                Cursor { blank: blanks, at: 0 }
+            }
+            Event::Quit => return Ok(None),
+            // Backspace at the start of a blank goes back into the one before it
+            Event::Backspace if cursor.at == 0 => previous,
+            Event::Backspace => {
+                answer.remove(byte(cursor.at - 1));
+                Cursor { at: cursor.at - 1, ..cursor }
+            }
+            Event::Delete if cursor.at < len => {
+                answer.remove(byte(cursor.at));
+                cursor
+            }
+            Event::Delete => cursor,
+            Event::Left if cursor.at == 0 => previous,
+            Event::Left => Cursor { at: cursor.at - 1, ..cursor },
+            Event::Right if cursor.at >= len && cursor.blank + 1 < blanks => Cursor { at: 0, ..following },
+            Event::Right => Cursor { at: cursor.at + 1, ..cursor },
+            Event::PreviousBlank => previous,
+            Event::NextBlank => following,
+            Event::Home => Cursor { at: 0, ..cursor },
+            Event::End => Cursor { at: len, ..cursor },
+        };
+
+        if next.blank != cursor.blank {
+            spent[cursor.blank] += entered.elapsed();
+            entered = time::Instant::now();
+        }
+        if let Some(answer) = answers.get(next.blank) {
+            next.at = next.at.min(answer.chars().count());
+        }
+        cursor = next;
+    }
+
+    let timings = if timed {
+        first_key
+            .into_iter()
+            .zip(spent)
+            .map(|(first_key, total)| Timing { first_key: first_key.unwrap_or(total), total })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    window.show_answers(question, &answers);
+
+    Ok(Some(Response { answers, timings, total: asked_at.elapsed() }))
+}
+
+/// Has the learner type out `correction` until they get it right, which doesn't depend on case. Gives back `false` if
+/// the learner stopped the session instead.
+pub fn type_out<R: Render>(window: &mut R, correction: &str) -> Result<bool, String> {
+    let mut typed = String::new();
+
+    loop {
+        window.show_prompt(&Prompt::Correction { correction, typed: &typed });
+
+        let finished = match window.next_event()? {
+            Event::Char(ch) => {
+                typed.push(ch);
+                false
+            }
+            Event::Backspace => {
+                typed.pop();
+                false
+            }
+            Event::Enter => true,
+            Event::Line(line) => {
+                typed = line;
+                true
+            }
+            Event::Quit => return Ok(false),
+            _ => false,
+        };
+
+        if finished {
+            if typed.trim().to_lowercase() == correction.trim().to_lowercase() {
+                return Ok(true);
+            }
+            typed.clear();
+        }
+    }
+}
+
+/// Asks how hard a correctly answered question was to remember. Gives back `None` if the learner stopped the session
+/// instead.
+pub fn rate_recall<R: Render>(window: &mut R) -> Result<Option<Rating>, String> {
+    loop {
+        window.show_prompt(&Prompt::Rating);
+
+        let choice = match window.next_event()? {
+            Event::Char(ch) => ch.to_string(),
+            Event::Line(line) => line,
+            Event::Quit => return Ok(None),
+            _ => continue,
+        };
+
+        match choice.trim() {
+            "1" => return Ok(Some(Rating::Hard)),
+            "2" => return Ok(Some(Rating::Good)),
+            "3" => return Ok(Some(Rating::Easy)),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! The Leitner system: every question lives in a numbered box, and each box is studied on its own cadence.
+//!
+//! Answering a question correctly moves it up a box, and answering it wrong sends it back to the first one. Like the
+//! spaced repetition schedule, which box a question is in is worked out from the progress file rather than being
+//! stored separately, but only from the attempts made in Leitner sessions. The progress file also keeps count of the
+//! Leitner sessions themselves, since the boxes that are due depend on it.
+
+use std::{fs, path::PathBuf};
+
+use crate::{progress::{Attempt, Progress}, question::Parser};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leitner {
+    /// `cadence[n]` is how many sessions apart box `n + 1` is studied.
+    cadence: Vec<u64>,
+}
+
+impl Default for Leitner {
+    fn default() -> Self {
+        Self { cadence: vec![1, 2, 4, 8, 16] }
+    }
+}
+
+impl Leitner {
+    /// Parses a cadence like `1,2,4,8`, with one number per box.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let cadence = s
+            .split(',')
+            .map(|n| match n.trim().parse::<u64>() {
+                Ok(0) | Err(_) => Err(format!("`{}` is not a valid number of sessions!", n.trim())),
+                Ok(n) => Ok(n),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { cadence })
+    }
+
+    pub fn boxes(&self) -> usize {
+        self.cadence.len()
+    }
+
+    /// How many sessions apart `box_number` is studied.
+    pub fn cadence(&self, box_number: usize) -> u64 {
+        self.cadence[box_number - 1]
+    }
+
+    /// The 1-based box a question is in after `attempts`. Questions that have never been asked start in box 1, and
+    /// neither retries within a session nor answers given outside of Leitner sessions move a question between boxes.
+    pub fn box_of(&self, attempts: &[Attempt]) -> usize {
+        attempts.iter().filter(|a| a.retry == 0 && a.leitner.is_some()).fold(1, |box_number, attempt| {
+            if attempt.correct {
+                (box_number + 1).min(self.boxes())
+            } else {
+                1
+            }
+        })
+    }
+
+    /// Whether `box_number` gets studied in the `session`th session, counting from 1.
+    pub fn is_due(&self, box_number: usize, session: u64) -> bool {
+        session.is_multiple_of(self.cadence(box_number))
+    }
+}
+
+/// Entry point for `quickstudy boxes [--progress <file>] [--cadence <n,n,...>] <files>`.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut progress_path = Progress::default_path();
+    let mut leitner = Leitner::default();
+    let mut paths = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--progress" => match args.next() {
+                Some(path) => progress_path = Some(PathBuf::from(path)),
+                None => return Err(String::from("Expected a file after `--progress`!")),
+            },
+            "--cadence" => match args.next() {
+                Some(cadence) => leitner = Leitner::parse(cadence)?,
+                None => return Err(String::from("Expected a cadence after `--cadence`!")),
+            },
+            _ => paths.push(arg),
+        }
+    }
+
+    let progress_path = progress_path.ok_or_else(|| String::from("Could not find the progress file!"))?;
+    let progress = Progress::read(&progress_path)?;
+
+    let mut boxes = vec![Vec::new(); leitner.boxes()];
+
+    for path in paths {
+        let src = fs::read_to_string(path).map_err(|e| format!("Could not read file `{path}`: {e}!"))?;
+        let mut parser = Parser::new(&src, path);
+
+        while let Some(maybe_question) = parser.next_located() {
+            if let Ok(q) = maybe_question {
+                let question = q.to_string();
+                let box_number = leitner.box_of(progress.attempts(&question));
+                boxes[box_number - 1].push(format!("{path}:{} {question}", parser.line_number()));
+            }
+        }
+    }
+
+    let session = progress.leitner_sessions() + 1;
+
+    for (idx, questions) in boxes.iter().enumerate() {
+        let box_number = idx + 1;
+        let due = if leitner.is_due(box_number, session) { ", due next session" } else { "" };
+
+        println!("Box {box_number} (every {} session(s){due}): {} question(s)", leitner.cadence(box_number), questions.len());
+        for question in questions {
+            println!("    {question}");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use std::collections::{self, HashMap};
+
+use crate::question::{Answer, Question};
+
+fn normalize(s: &str) -> String {
+    s.trim().to_lowercase()
+}
+
+/// Looks for things that parse fine but are almost certainly mistakes.
+///
+/// A `Linter` remembers every question it has seen, so that duplicates can be found across files.
+#[derive(Debug, Default)]
+pub struct Linter {
+    seen: HashMap<String, (String, usize)>,
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn lint(&mut self, question: &Question, path: &str, line: usize) -> Vec<String> {
+        let mut warnings = lint_question(question);
+
+        match self.seen.entry(normalize(&question.to_string())) {
+            collections::hash_map::Entry::Occupied(e) => {
+                let (other_path, other_line) = e.get();
+                warnings.push(format!("Duplicate of the question at {other_path}:{other_line}!"));
+            }
+            collections::hash_map::Entry::Vacant(e) => {
+                e.insert((path.to_string(), line));
+            }
+        }
+
+        warnings
+    }
+}
+
+pub fn lint_question(question: &Question) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut slots_per_pool = vec![0; question.pools.len()];
+    let mut has_blanks = false;
+
+    for answer in question.dat.iter().filter_map(|(_, ans)| ans.as_ref()) {
+        has_blanks = true;
+
+        match answer {
+            Answer::Raw(raw) => {
+                if raw.trim().is_empty() {
+                    warnings.push(String::from("Empty answer!"));
+                }
+            }
+            Answer::OneOf(options) => {
+                let mut seen = collections::HashSet::new();
+                for option in options {
+                    if option.trim().is_empty() {
+                        warnings.push(format!("Empty alternative in `[{}]`!", options.join(" | ")));
+                    } else if !seen.insert(normalize(option)) {
+                        warnings.push(format!("Alternative `{option}` is listed more than once!"));
+                    }
+                }
+            }
+            // The parser makes sure that every set used is there
+            Answer::SharedPool(idx) => slots_per_pool[*idx] += 1,
+        }
+    }
+
+    if !has_blanks {
+        warnings.push(String::from("Question has no blanks to fill in!"));
+    }
+
+    for (idx, (pool, slots)) in question.pools.iter().zip(slots_per_pool).enumerate() {
+        if slots > pool.len() {
+            warnings.push(format!(
+                "Set #{} has {} items, but is used by {slots} blanks!",
+                idx + 1,
+                pool.len()
+            ));
+        }
+
+        let mut seen = collections::HashSet::new();
+        for item in pool {
+            if item.trim().is_empty() {
+                warnings.push(format!("Empty item in set #{}!", idx + 1));
+            } else if !seen.insert(normalize(item)) {
+                warnings.push(format!("Item `{item}` is listed more than once in set #{}!", idx + 1));
+            }
+        }
+    }
+
+    warnings
+}
@@ -0,0 +1,313 @@
+//! A small language server for study files, spoken over stdin and stdout.
+//!
+//! Only full document syncing is supported, since study files are small and every line is parsed on its own anyway.
+
+use std::{collections::HashMap, io::{self, BufRead, Write}};
+
+use serde_json::{json, Value};
+
+use crate::{check::{self, Severity}, lint::Linter, question::{Answer, Parser, Question}, syntax::Blank};
+
+pub fn read_message<R: BufRead>(reader: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Missing Content-Length header!"))?;
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    serde_json::from_slice(&body).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    writer.flush()
+}
+
+/// Converts a byte offset into `line` to the UTF-16 offset that LSP positions use.
+fn to_utf16(line: &str, byte: usize) -> usize {
+    line[..byte.min(line.len())].encode_utf16().count()
+}
+
+fn from_utf16(line: &str, character: usize) -> usize {
+    let mut units = 0;
+    for (idx, ch) in line.char_indices() {
+        if units >= character {
+            return idx;
+        }
+        units += ch.len_utf16();
+    }
+    line.len()
+}
+
+fn range(line_number: usize, line: &str, start: usize, end: usize) -> Value {
+    json!({
+        "start": { "line": line_number, "character": to_utf16(line, start) },
+        "end": { "line": line_number, "character": to_utf16(line, end) },
+    })
+}
+
+/// Byte ranges of the pools at the end of a line, found without parsing it so that half-written lines still work.
+fn pool_ranges(line: &str) -> Vec<(usize, usize)> {
+    let mut depth: usize = 0;
+    let mut starts = Vec::new();
+
+    for (idx, ch) in line.char_indices() {
+        match ch {
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth = depth.saturating_sub(1),
+            ';' if depth == 0 => starts.push(idx + 1),
+            _ => {}
+        }
+    }
+
+    let mut ranges = Vec::new();
+    for (pos, start) in starts.iter().enumerate() {
+        let end = starts.get(pos + 1).map(|next| next - 1).unwrap_or(line.len());
+        let text = &line[*start..end];
+        let trimmed_start = start + (text.len() - text.trim_start().len());
+        ranges.push((trimmed_start, trimmed_start + text.trim().len()));
+    }
+    ranges
+}
+
+fn describe_grading(question: &Question) -> String {
+    let mut s = String::new();
+
+    for (number, answer) in question.dat.iter().filter_map(|(_, ans)| ans.as_ref()).enumerate() {
+        let accepted = match answer {
+            Answer::Raw(raw) => format!("`{raw}`"),
+            Answer::OneOf(options) => options.iter().map(|o| format!("`{o}`")).collect::<Vec<_>>().join(" or "),
+            Answer::SharedPool(idx) => match question.pools.get(*idx) {
+                Some(pool) => format!(
+                    "any unused item of set #{}: {}",
+                    idx + 1,
+                    pool.iter().map(|o| format!("`{o}`")).collect::<Vec<_>>().join(", ")
+                ),
+                None => format!("set #{}, which doesn't exist", idx + 1),
+            },
+        };
+        s.push_str(&format!("{}. {accepted}\n", number + 1));
+    }
+
+    s
+}
+
+#[derive(Debug, Default)]
+pub struct Server {
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn line(&self, params: &Value) -> Option<(&str, usize, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let line_number = params["position"]["line"].as_u64()? as usize;
+        let character = params["position"]["character"].as_u64()? as usize;
+
+        let line = self.documents.get(uri)?.lines().nth(line_number)?;
+        Some((line, line_number, from_utf16(line, character)))
+    }
+
+    fn diagnostics(&self, uri: &str) -> Value {
+        let text = self.documents.get(uri).map(String::as_str).unwrap_or("");
+        let lines = text.lines().collect::<Vec<_>>();
+
+        let diagnostics = check::check_source(text, uri, &mut Linter::new())
+            .into_iter()
+            .map(|d| {
+                let line = lines.get(d.line - 1).copied().unwrap_or("");
+                json!({
+                    "range": range(d.line - 1, line, d.column - 1, line.len()),
+                    "severity": match d.severity {
+                        Severity::Error => 1,
+                        Severity::Warning => 2,
+                    },
+                    "source": "quickstudy",
+                    "message": d.message,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        })
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let Some((line, _, _)) = self.line(params) else {
+            return Value::Null;
+        };
+
+        match Parser::new(line, "").next() {
+            Some(Ok(q)) => {
+                json!({
+                    "contents": {
+                        "kind": "markdown",
+                        "value": format!("**Asked as:** {}\n\n**Accepted answers:**\n\n{}", q.prompt(), describe_grading(&q)),
+                    }
+                })
+            }
+            _ => Value::Null,
+        }
+    }
+
+    fn completion(&self, params: &Value) -> Value {
+        let Some((line, _, byte)) = self.line(params) else {
+            return json!([]);
+        };
+
+        let after_brace = line[..byte].ends_with('{');
+
+        let items = pool_ranges(line)
+            .into_iter()
+            .enumerate()
+            .map(|(idx, (start, end))| {
+                json!({
+                    "label": format!("{{{}}}", idx + 1),
+                    "detail": line[start..end].to_string(),
+                    "insertText": if after_brace { format!("{}}}", idx + 1) } else { format!("{{{}}}", idx + 1) },
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Value::Array(items)
+    }
+
+    fn definition(&self, params: &Value) -> Value {
+        let Some((line, line_number, byte)) = self.line(params) else {
+            return Value::Null;
+        };
+
+        // Parsing the line on its own makes all the spans relative to the start of it.
+        let Some(Ok(syntax)) = Parser::new(line, "").next_syntax() else {
+            return Value::Null;
+        };
+
+        let pool = match syntax.blank_at(byte) {
+            Some(Blank::Pool { number, .. }) => syntax.pools.get(number - 1),
+            _ => None,
+        };
+
+        match pool {
+            Some(pool) => json!({
+                "uri": params["textDocument"]["uri"],
+                "range": range(line_number, line, pool.span.start, pool.span.end),
+            }),
+            None => Value::Null,
+        }
+    }
+
+    /// Handles one incoming message, returning everything that should be sent back.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("").to_string();
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "completionProvider": { "triggerCharacters": ["{"] },
+                    "definitionProvider": true,
+                },
+                "serverInfo": { "name": "quickstudy" },
+            }),
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or("").to_string();
+                self.documents.insert(uri.clone(), text);
+                return vec![self.diagnostics(&uri)];
+            }
+            "textDocument/didChange" => {
+                if let Some(text) = params["contentChanges"].as_array().and_then(|c| c.last()).and_then(|c| c["text"].as_str()) {
+                    self.documents.insert(uri.clone(), text.to_string());
+                }
+                return vec![self.diagnostics(&uri)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": uri, "diagnostics": [] },
+                })];
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/definition" => self.definition(params),
+            _ => {
+                // Notifications we don't care about don't get an answer
+                if message.get("id").is_none() {
+                    return Vec::new();
+                }
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "error": { "code": -32601, "message": format!("Unknown method `{method}`!") },
+                })];
+            }
+        };
+
+        vec![json!({ "jsonrpc": "2.0", "id": message["id"], "result": result })]
+    }
+
+    /// Serves requests until the client sends `exit` or closes the stream.
+    pub fn serve<R: BufRead, W: Write>(&mut self, mut input: R, mut output: W) -> io::Result<bool> {
+        while let Some(message) = read_message(&mut input)? {
+            if message["method"] == "exit" {
+                return Ok(self.shutdown);
+            }
+
+            for response in self.handle(&message) {
+                write_message(&mut output, &response)?;
+            }
+        }
+        Ok(self.shutdown)
+    }
+}
+
+/// Entry point for `quickstudy lsp`.
+pub fn run() -> Result<(), String> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    let clean = Server::new()
+        .serve(stdin.lock(), stdout.lock())
+        .map_err(|e| format!("Language server failed: {e}!"))?;
+
+    if clean {
+        Ok(())
+    } else {
+        Err(String::from("Language server exited without being shut down!"))
+    }
+}
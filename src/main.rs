@@ -1,15 +1,44 @@
-use std::{env, fs};
+use std::{env, fs, path::PathBuf, str, time};
 
+mod check;
+mod format;
+mod input;
+mod leitner;
+mod lint;
+mod lsp;
+mod progress;
 mod render;
 mod question;
+mod random;
+mod report;
+mod schedule;
+mod session;
+mod stats;
+mod summary;
+mod syntax;
+#[cfg(any(feature = "tui", feature = "crossterm-tui"))]
+mod theme;
 mod tests;
 
-use question::*;
+use leitner::Leitner;
+use progress::Progress;
+use session::{Options, Session, Shuffle};
 #[cfg(feature = "tui")]
 use render::{NCurses, Render};
-#[cfg(not(feature = "tui"))]
+#[cfg(all(feature = "crossterm-tui", not(feature = "tui")))]
+use render::{Crossterm, Render};
+#[cfg(not(any(feature = "tui", feature = "crossterm-tui")))]
 use render::{Render, Cli};
 
+/// Parses the value given to `flag`.
+fn number_arg<T: str::FromStr>(value: Option<String>, flag: &str) -> Result<T, String> {
+    match value.map(|n| n.parse::<T>()) {
+        Some(Ok(n)) => Ok(n),
+        Some(Err(_)) => Err(format!("`{flag}` needs a whole number!")),
+        None => Err(format!("Expected a number after `{flag}`!")),
+    }
+}
+
 fn main() -> Result<(), String> {
     let mut args = env::args();
 
@@ -17,33 +46,116 @@ fn main() -> Result<(), String> {
 
     let args = args.collect::<Vec<String>>();
 
-    #[cfg(feature = "tui")]
-    let mut window = NCurses::init()?;
-    #[cfg(not(feature = "tui"))]
-    let mut window = Cli::init()?;
+    match args.first().map(String::as_str) {
+        Some("check") => return check::run(&args[1..]),
+        Some("fmt") => return format::run(&args[1..]),
+        Some("boxes") => return leitner::run(&args[1..]),
+        Some("stats") => return stats::run(&args[1..]),
+        Some("lsp") => return lsp::run(),
+        _ => {}
+    }
+
+    let mut options = Options::default();
+    let mut one_line = false;
+    let mut report_path = None;
+    let mut summary_path = None;
+    let mut progress_path = Progress::default_path();
+    let mut paths = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lint" => options.lint = true,
+            "--srs" => options.srs = true,
+            "--leitner" => {
+                options.leitner.get_or_insert_with(Leitner::default);
+            }
+            "--cadence" => match args.next() {
+                Some(cadence) => options.leitner = Some(Leitner::parse(&cadence)?),
+                None => return Err(String::from("Expected a cadence after `--cadence`!")),
+            },
+            "--shuffle" => options.shuffle = Shuffle::WithinFiles,
+            "--shuffle-all" => options.shuffle = Shuffle::AcrossFiles,
+            "--shuffle-choices" => options.shuffle_choices = true,
+            "--retries" => options.max_retries = number_arg(args.next(), &arg)?,
+            "--retry-gap" => options.retry_gap = number_arg(args.next(), &arg)?,
+            "--seed" => options.seed = Some(number_arg(args.next(), &arg)?),
+            "--limit" => options.question_limit = Some(number_arg(args.next(), &arg)?),
+            "--minutes" => options.time_limit = Some(time::Duration::from_secs(number_arg::<u64>(args.next(), &arg)? * 60)),
+            "--due-only" => options.due_only = true,
+            "--summary-json" => match args.next() {
+                Some(path) => summary_path = Some(PathBuf::from(path)),
+                None => return Err(String::from("Expected a file after `--summary-json`!")),
+            },
+            "--exam" => options.exam = true,
+            "--report" => match args.next() {
+                Some(path) => report_path = Some(PathBuf::from(path)),
+                None => return Err(String::from("Expected a file after `--report`!")),
+            },
+            "--new-per-day" => options.new_per_day = Some(number_arg(args.next(), &arg)?),
+            "--slow" => match args.next().map(|seconds| seconds.parse().map(time::Duration::try_from_secs_f64)) {
+                Some(Ok(Ok(limit))) => options.slow_after = Some(limit),
+                Some(_) => return Err(format!("`{arg}` needs a positive number of seconds!")),
+                None => return Err(format!("Expected a number of seconds after `{arg}`!")),
+            },
+            "--progress" => match args.next() {
+                Some(path) => progress_path = Some(PathBuf::from(path)),
+                None => return Err(String::from("Expected a file after `--progress`!")),
+            },
+            "--no-progress" => progress_path = None,
+            "--one-line" => one_line = true,
+            _ => paths.push(arg),
+        }
+    }
+
+    if report_path.is_some() && !options.exam {
+        return Err(String::from("`--report` can only be used with `--exam`!"));
+    }
+
+    if options.srs && options.leitner.is_some() {
+        return Err(String::from("Only one of `--srs` and `--leitner` can be used at a time!"));
+    }
+
+    // Full screen renderers always answer blanks in place
+    if one_line && cfg!(any(feature = "tui", feature = "crossterm-tui")) {
+        return Err(String::from("`--one-line` only works with the line-by-line interface, built with `--no-default-features`!"));
+    }
 
-    for path in args {
+    let mut files = Vec::new();
+    for path in paths {
         match fs::read_to_string(&path) {
             Err(e) => {
                 return Err(format!("Could not read file `{path}`: {e}!"));
             }
-            Ok(src) => {
-                for maybe_question in Parser::new(&src, &path) {
-                    match maybe_question {
-                        Err(e) => {
-                            eprintln!("{e}");
-                            window.display_error(&e);
-                        },
-                        Ok(q) => {
-                            let answers = window.ask(q.renderable())?;
-                            let correction = q.check_answers(answers);
-                            window.show_result(correction);
-                        }
-                    }
-                }
-            }
+            Ok(src) => files.push((path, src)),
         }
     }
 
+    let progress = match progress_path {
+        Some(path) => Some(Progress::open(&path)?),
+        None => None,
+    };
+
+    let mut session = Session::new(files, options, progress)?;
+
+    #[cfg(feature = "tui")]
+    let mut window = NCurses::init()?;
+    #[cfg(all(feature = "crossterm-tui", not(feature = "tui")))]
+    let mut window = Crossterm::init()?;
+    #[cfg(not(any(feature = "tui", feature = "crossterm-tui")))]
+    let mut window = Cli::init()?.one_line(one_line);
+
+    session.run(&mut window)?;
+
+    if let Some(path) = report_path {
+        fs::write(&path, session.report().to_string())
+            .map_err(|e| format!("Could not write report to `{}`: {e}!", path.display()))?;
+    }
+
+    if let Some(path) = summary_path {
+        fs::write(&path, session.summary().to_json().to_string())
+            .map_err(|e| format!("Could not write summary to `{}`: {e}!", path.display()))?;
+    }
+
     Ok(())
 }
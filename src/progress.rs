@@ -0,0 +1,205 @@
+//! Remembers every answer ever given, so that progress carries over between sessions.
+//!
+//! The database is a file with one JSON object per line, and every attempt is appended and synced to disk as soon as
+//! it's made. If quickstudy dies halfway through writing a line, that line is skipped the next time the file is
+//! loaded, and everything before it is still there. Besides attempts, a line is saved whenever a Leitner session
+//! starts, so that sessions where nothing was due still count.
+
+use std::{collections::HashMap, env, fs, io::{self, Write}, path::{Path, PathBuf}, time};
+
+use serde_json::{json, Value};
+
+use crate::schedule::Rating;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attempt {
+    /// The question in its canonical form, which is what identifies it between sessions.
+    pub question: String,
+    pub file: String,
+    /// When the session this attempt was made in started, which identifies the session.
+    pub session: u64,
+    /// Seconds since the unix epoch.
+    pub time: u64,
+    pub correct: bool,
+    /// The fraction of blanks that were answered correctly.
+    pub score: f64,
+    pub answers: Vec<String>,
+    /// How hard the learner said it was to remember, if they were asked.
+    pub rating: Option<Rating>,
+    /// How many times the question had already been missed and asked again in the same session.
+    pub retry: u32,
+    /// The number of the Leitner session it was asked in, if it was studied with Leitner boxes.
+    pub leitner: Option<u64>,
+}
+
+impl Attempt {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "question": self.question,
+            "file": self.file,
+            "session": self.session,
+            "time": self.time,
+            "correct": self.correct,
+            "score": self.score,
+            "answers": self.answers,
+            "rating": self.rating.map(|r| r.to_string()),
+            "retry": self.retry,
+            "leitner": self.leitner,
+        })
+    }
+
+    pub fn from_json(value: &Value) -> Option<Self> {
+        let time = value["time"].as_u64()?;
+
+        Some(Self {
+            question: value["question"].as_str()?.to_string(),
+            file: value["file"].as_str()?.to_string(),
+            // Attempts saved before sessions were tracked count as their own session.
+            session: value["session"].as_u64().unwrap_or(time),
+            time,
+            correct: value["correct"].as_bool()?,
+            score: value["score"].as_f64()?,
+            answers: value["answers"]
+                .as_array()?
+                .iter()
+                .map(|a| a.as_str().map(ToString::to_string))
+                .collect::<Option<_>>()?,
+            rating: value["rating"].as_str().and_then(Rating::parse),
+            retry: value["retry"].as_u64().unwrap_or(0) as u32,
+            leitner: value["leitner"].as_u64(),
+        })
+    }
+}
+
+pub const DAY: u64 = 60 * 60 * 24;
+
+pub fn now() -> u64 {
+    time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[derive(Debug)]
+pub struct Progress {
+    file: fs::File,
+    history: HashMap<String, Vec<Attempt>>,
+    /// How many Leitner sessions have been started, whether or not anything was asked in them.
+    leitner_sessions: u64,
+}
+
+impl Progress {
+    /// `$XDG_DATA_HOME/quickstudy/progress.jsonl`, falling back to `~/.local/share` as the XDG base directory spec says.
+    pub fn default_path() -> Option<PathBuf> {
+        let data_dir = match env::var_os("XDG_DATA_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".local").join("share"),
+        };
+        Some(data_dir.join("quickstudy").join("progress.jsonl"))
+    }
+
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let display = path.display();
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Could not create directory for `{display}`: {e}!"))?;
+        }
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("Could not read progress file `{display}`: {e}!")),
+        };
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Could not open progress file `{display}`: {e}!"))?;
+
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            // A crash cut off the last attempt, so start the next one on a fresh line.
+            file.write_all(b"\n").map_err(|e| format!("Could not write progress file `{display}`: {e}!"))?;
+        }
+
+        Ok(Self::parse(file, &contents))
+    }
+
+    /// Reads the progress file at `path` without creating or changing anything, for commands that only look at it.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let display = path.display();
+
+        let contents = fs::read_to_string(path).map_err(|e| format!("Could not read progress file `{display}`: {e}!"))?;
+        let file = fs::File::open(path).map_err(|e| format!("Could not open progress file `{display}`: {e}!"))?;
+
+        Ok(Self::parse(file, &contents))
+    }
+
+    fn parse(file: fs::File, contents: &str) -> Self {
+        let mut history: HashMap<String, Vec<Attempt>> = HashMap::new();
+        let mut leitner_sessions = 0;
+
+        for line in contents.lines() {
+            let Ok(value) = serde_json::from_str::<Value>(line) else {
+                continue;
+            };
+
+            if let Some(session) = value["leitner_session"].as_u64() {
+                leitner_sessions = leitner_sessions.max(session);
+            } else if let Some(attempt) = Attempt::from_json(&value) {
+                history.entry(attempt.question.clone()).or_default().push(attempt);
+            }
+        }
+
+        Self { file, history, leitner_sessions }
+    }
+
+    fn append(&mut self, value: &Value) -> Result<(), String> {
+        let line = format!("{value}\n");
+
+        self.file
+            .write_all(line.as_bytes())
+            .and_then(|_| self.file.sync_data())
+            .map_err(|e| format!("Could not save progress: {e}!"))
+    }
+
+    pub fn record(&mut self, attempt: Attempt) -> Result<(), String> {
+        self.append(&attempt.to_json())?;
+
+        self.history.entry(attempt.question.clone()).or_default().push(attempt);
+        Ok(())
+    }
+
+    /// How many Leitner sessions have been started before.
+    pub fn leitner_sessions(&self) -> u64 {
+        self.leitner_sessions
+    }
+
+    /// Saves that another Leitner session has started, even if nothing turns out to be due in it, and gives back its
+    /// number, counting from 1.
+    pub fn start_leitner_session(&mut self) -> Result<u64, String> {
+        let session = self.leitner_sessions + 1;
+        self.append(&json!({ "leitner_session": session, "time": now() }))?;
+
+        self.leitner_sessions = session;
+        Ok(session)
+    }
+
+    /// How many questions were asked for the first time at or after `time`.
+    pub fn introduced_since(&self, time: u64) -> usize {
+        self.history
+            .values()
+            .filter(|attempts| attempts.first().is_some_and(|first| first.time >= time))
+            .count()
+    }
+
+    /// Every question that has been attempted, along with all of its attempts.
+    pub fn all(&self) -> impl Iterator<Item = (&str, &[Attempt])> {
+        self.history.iter().map(|(question, attempts)| (question.as_str(), attempts.as_slice()))
+    }
+
+    /// Every attempt at `question`, oldest first.
+    pub fn attempts(&self, question: &str) -> &[Attempt] {
+        self.history.get(question).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
@@ -1,110 +1,171 @@
 use std::{collections, fmt, iter};
 
-#[derive(Debug, PartialEq, Eq)]
+use crate::{random::Rng, render::{BlankKind, BlankView, Part, QuestionView}, syntax::{Blank, Item, Pool, QuestionSyntax, Text}};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Answer {
     Raw(String),
     SharedPool(usize), // Index into list of list of options.
     OneOf(Vec<String>),
 }
 
+/// Writes the answer back out the way it would appear in a source file.
 impl fmt::Display for Answer {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Answer::Raw(s) => write!(f, "{s}"),
-            Answer::SharedPool(idx) => write!(f, "{{one of the #{} set}}", idx + 1),
+            Answer::Raw(s) => write!(f, "[{}]", s.trim()),
+            Answer::SharedPool(idx) => write!(f, "{{{}}}", idx + 1),
             Answer::OneOf(v) => {
+                write!(f, "[")?;
                 for (idx, possible_answer) in v.iter().enumerate() {
                     if idx > 0 {
-                        write!(f, " OR ")?;
+                        write!(f, " | ")?;
                     }
-                    write!(f, "{possible_answer}")?;
+                    write!(f, "{}", possible_answer.trim())?;
                 }
-                Ok(())
+                write!(f, "]")
             }
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+impl Answer {
+    /// Describes the answer to someone who got it wrong.
+    pub fn describe(&self) -> String {
+        match self {
+            Answer::Raw(s) => s.to_string(),
+            Answer::SharedPool(idx) => format!("{{one of the #{} set}}", idx + 1),
+            Answer::OneOf(v) => v.join(" OR "),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Question {
     pub dat: Vec<(Option<String>, Option<Answer>)>,
     pub pools: Vec<Vec<String>>,
 }
 
-type SrcIter<'a> = iter::Filter<iter::Enumerate<std::str::Lines<'a>>, &'static dyn Fn(&(usize, &str)) -> bool>;
+type SrcIter<'a> = iter::Enumerate<std::str::Lines<'a>>;
 
 #[derive(Debug)]
 pub struct Parser<'a> {
     src_name: &'a str,
+    full_src: &'a str,
     src: SrcIter<'a>,
+    line: &'a str,
     current_line: iter::Peekable<std::str::CharIndices<'a>>,
+    line_number: usize,
+    line_offset: usize, // Where the current line starts in `full_src`
 }
 
 pub type ParseResult<T> = Result<T, (usize, String)>;
 
-fn is_valid_line((_, line): &(usize, &str)) -> bool {
-    !line.trim().is_empty() && !line.trim().starts_with('#')
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,   // 1-based line in the source
+    pub column: usize, // 1-based byte offset into the line
+    pub message: String,
+}
+
+/// One line of a source file, including the ones that aren't questions.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Line<'a> {
+    Blank,
+    Comment(&'a str),
+    Question(Result<QuestionSyntax, ParseError>),
 }
 
 impl<'a> Parser<'a> {
     pub fn new(src: &'a str, src_name: &'a str) -> Self {
         Self {
             src_name,
-            src: src.lines().enumerate().filter(&is_valid_line as &'static dyn Fn(&(usize, &str)) -> bool),
+            full_src: src,
+            src: src.lines().enumerate(),
+            line: "",
             current_line: "".char_indices().peekable(), // Never will be touched, and if it is, it'll throw an error.
+            line_number: 0,
+            line_offset: 0,
         }
     }
 
-    fn parse_text(&mut self) -> ParseResult<String> {
+    /// The index of the next character in the current line, or its length if there are none left.
+    fn position(&mut self) -> usize {
+        self.current_line.peek().map(|x| x.0).unwrap_or(self.line.len())
+    }
+
+    /// Turns the part of the current line between `start` and `end` into an `Item`, trimming it.
+    fn item(&self, start: usize, end: usize) -> Item {
+        let raw = &self.line[start..end];
+        let start = start + (raw.len() - raw.trim_start().len());
+        let text = raw.trim();
+
+        Item {
+            span: self.line_offset + start..self.line_offset + start + text.len(),
+            text: text.to_string(),
+        }
+    }
+
+    fn parse_text(&mut self) -> ParseResult<Text> {
+        let start = self.position();
         let mut text = String::new();
 
         while let Some((idx, ch)) = self.current_line.peek() {
             match ch {
-                '[' | ';' | '{' => return Ok(text),
+                '[' | ';' | '{' => break,
                 ']' => return Err((*idx, String::from("Unexpected `]`!"))),
                 _ => text.push(self.current_line.next().unwrap().1),
             }
         }
-        Ok(text)
+
+        let end = self.position();
+        Ok(Text { span: self.line_offset + start..self.line_offset + end, text })
     }
 
-    fn parse_answer_pools(&mut self) -> ParseResult<Vec<Vec<String>>> {
+    fn parse_answer_pools(&mut self) -> ParseResult<Vec<Pool>> {
         assert_eq!(self.current_line.next().map(|x| x.1), Some(';'));
 
         let mut pools = Vec::new();
         let mut current_pool = Vec::new();
         let mut current_string = String::new();
+        let mut pool_start = self.position();
+        let mut item_start = pool_start;
 
-        for (idx, ch) in &mut self.current_line {
+        while let Some((idx, ch)) = self.current_line.next() {
             match ch {
                 ';' => {
                     if current_pool.is_empty() && current_string.is_empty() {
                         return Err((idx, "Pool cannot be empty!".to_string()));
                     }
                     if !current_string.is_empty() {
-                        current_pool.push(current_string);
+                        current_pool.push(self.item(item_start, idx));
                         current_string = String::new();
                     }
-                    pools.push(current_pool);
+                    pools.push(Pool { span: self.item(pool_start, idx).span, items: current_pool });
                     current_pool = Vec::new();
+                    pool_start = idx + 1;
+                    item_start = idx + 1;
                 }
                 ',' => {
-                    current_pool.push(current_string);
+                    current_pool.push(self.item(item_start, idx));
                     current_string = String::new();
+                    item_start = idx + 1;
                 }
                 _ => current_string.push(ch),
             }
         }
+
+        let end = self.line.len();
         if !current_string.is_empty() {
-            current_pool.push(current_string);
+            current_pool.push(self.item(item_start, end));
         }
         if !current_pool.is_empty() {
-            pools.push(current_pool);
+            pools.push(Pool { span: self.item(pool_start, end).span, items: current_pool });
         }
         Ok(pools)
     }
 
-    fn parse_idx_answer(&mut self) -> ParseResult<usize> {
+    fn parse_idx_answer(&mut self) -> ParseResult<Blank> {
         let start = self.current_line.next();
         assert_eq!(start.map(|x| x.1), Some('{'));
 
@@ -112,13 +173,23 @@ impl<'a> Parser<'a> {
 
         let mut t = String::new();
 
-        for (_, ch) in self.current_line.by_ref() {
+        for (idx, ch) in self.current_line.by_ref() {
             match ch {
                 '}' => {
-                    return t
+                    let number = t
                         .trim()
                         .parse::<usize>()
-                        .map_err(|_| (start, "Not a number!".to_string()));
+                        .map_err(|_| (start, "Not a number!".to_string()))?;
+
+                    if number == 0 {
+                        return Err((start, "Sets are numbered starting from 1!".to_string()));
+                    }
+
+                    return Ok(Blank::Pool {
+                        span: self.line_offset + start..self.line_offset + idx + 1,
+                        number,
+                        number_span: self.item(start + 1, idx).span,
+                    });
                 }
                 _ => t.push(ch),
             }
@@ -128,13 +199,16 @@ impl<'a> Parser<'a> {
 
     fn parse_answer(
         &mut self,
-        promised_idxs: &mut collections::HashSet<usize>,
-    ) -> ParseResult<Answer> {
+        promised_idxs: &mut collections::HashMap<usize, usize>,
+    ) -> ParseResult<Blank> {
 
         if self.current_line.peek().map(|x| x.1) == Some('{') {
-            let idx = self.parse_idx_answer()? - 1;
-            promised_idxs.insert(idx);
-            return Ok(Answer::SharedPool(idx));
+            let blank = self.parse_idx_answer()?;
+            if let Blank::Pool { number, ref span, .. } = blank {
+                // Remembers where the set was first used, to point there if it doesn't exist
+                promised_idxs.entry(number - 1).or_insert(span.start - self.line_offset);
+            }
+            return Ok(blank);
         }
 
         let start = self.current_line.next();
@@ -143,34 +217,34 @@ impl<'a> Parser<'a> {
         let start_idx = start.unwrap().0;
 
         let mut possible_answers = vec![];
-        let mut current_answer = String::new();
+        let mut answer_start = start_idx + 1;
 
-        for (idx, ch) in self.current_line.by_ref() {
+        while let Some((idx, ch)) = self.current_line.next() {
             match ch {
                 '|' => {
-                    possible_answers.push(current_answer.trim().to_string());
-                    current_answer = String::new();
+                    possible_answers.push(self.item(answer_start, idx));
+                    answer_start = idx + 1;
                 }
                 ']' => {
-                    if possible_answers.is_empty() {
-                        return Ok(Answer::Raw(current_answer.trim().to_string()));
-                    } else {
-                        possible_answers.push(current_answer.trim().to_string());
-                        return Ok(Answer::OneOf(possible_answers));
-                    }
+                    possible_answers.push(self.item(answer_start, idx));
+                    return Ok(Blank::Bracketed {
+                        span: self.line_offset + start_idx..self.line_offset + idx + 1,
+                        alternatives: possible_answers,
+                    });
                 }
                 '[' => return Err((idx, String::from("Unexpected `[`!"))),
-                _ => current_answer.push(ch),
+                _ => {}
             }
         }
         Err((start_idx, String::from("Unexpected end of answer!")))
     }
 
-    fn parse_question(&mut self, line: &'a str) -> ParseResult<Question> {
-        let mut dat: Vec<(Option<String>, Option<Answer>)> = Vec::new();
-        let mut promised_idxs = collections::HashSet::new();
+    fn parse_question(&mut self, line: &'a str) -> ParseResult<QuestionSyntax> {
+        let mut parts: Vec<(Option<Text>, Option<Blank>)> = Vec::new();
+        let mut promised_idxs = collections::HashMap::new();
         let mut pools = None;
 
+        self.line = line;
         self.current_line = line.char_indices().peekable();
 
         let mut last_idx = 0;
@@ -179,12 +253,7 @@ impl<'a> Parser<'a> {
         while let Some((idx, ch)) = self.current_line.peek() {
             last_idx = *idx;
             match ch {
-                '[' => dat.push((None, Some(self.parse_answer(&mut promised_idxs)?))),
-                '{' => {
-                    let idx = self.parse_idx_answer()? - 1;
-                    dat.push((None, Some(Answer::SharedPool(idx))));
-                    promised_idxs.insert(idx);
-                }
+                '[' | '{' => parts.push((None, Some(self.parse_answer(&mut promised_idxs)?))),
                 ';' => {
                     pool_idx = Some(*idx);
                     pools = Some(self.parse_answer_pools()?);
@@ -205,10 +274,10 @@ impl<'a> Parser<'a> {
                             '[' | '{' => Some(self.parse_answer(&mut promised_idxs)?),
                             _ => unreachable!("The only things that would stop the parsing of text have already been matched")
                         };
-                        dat.push((Some(text), ans));
+                        parts.push((Some(text), ans));
                     } else {
                         // finished
-                        dat.push((Some(text), None));
+                        parts.push((Some(text), None));
                     }
                 }
             }
@@ -236,16 +305,65 @@ impl<'a> Parser<'a> {
                     )));
                 }
             }
+
+            let missing = promised_idxs.iter().filter(|(idx, _)| **idx >= pools.len()).min_by_key(|(_, start)| **start);
+            if let Some((idx, start)) = missing {
+                return Err((*start, format!(
+                    "`{{{}}}` refers to set #{}, but there are only {} sets!",
+                    idx + 1,
+                    idx + 1,
+                    pools.len()
+                )));
+            }
+        }
+
+        Ok(QuestionSyntax {
+            span: self.line_offset..self.line_offset + line.len(),
+            parts,
+            pools: pools.unwrap_or_default(),
+        })
+    }
+}
+
+impl<'a> Parser<'a> {
+    /// Returns every line in the source, keeping blank lines and comments around.
+    pub fn next_line(&mut self) -> Option<Line<'a>> {
+        let (line_number, line) = self.src.next()?;
+        self.line_number = line_number + 1;
+        // `lines` only ever gives out slices of the source, so this is where the line starts in it.
+        self.line_offset = line.as_ptr() as usize - self.full_src.as_ptr() as usize;
+
+        if line.trim().is_empty() {
+            return Some(Line::Blank);
         }
+        if line.trim().starts_with('#') {
+            return Some(Line::Comment(line));
+        }
+
+        Some(Line::Question(self.parse_question(line).map_err(|(idx, message)| ParseError {
+            line: self.line_number,
+            column: idx + 1,
+            message,
+        })))
+    }
 
-        let mut pools = pools.unwrap_or_default();
-        for pool in &mut pools {
-            for item in pool {
-                *item = item.trim().to_string();
+    /// Like `next`, but keeps the syntax tree and the position of an error separate from its message.
+    pub fn next_syntax(&mut self) -> Option<Result<QuestionSyntax, ParseError>> {
+        loop {
+            if let Line::Question(res) = self.next_line()? {
+                return Some(res);
             }
         }
+    }
+
+    /// Like `next`, but keeps the position of an error separate from its message.
+    pub fn next_located(&mut self) -> Option<Result<Question, ParseError>> {
+        Some(self.next_syntax()?.map(|syntax| syntax.to_question()))
+    }
 
-        Ok(Question { dat, pools })
+    /// The 1-based line number of the question that was parsed last.
+    pub fn line_number(&self) -> usize {
+        self.line_number
     }
 }
 
@@ -253,30 +371,33 @@ impl<'a> iter::Iterator for Parser<'a> {
     type Item = Result<Question, String>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let (line_number, line) = self.src.next()?;
-
-        Some(self.parse_question(line).map_err(|(idx, msg)| format!("{}:{}:{} {msg}", self.src_name, line_number + 1, idx + 1)))
+        Some(self.next_located()?.map_err(|e| format!("{}:{}:{} {}", self.src_name, e.line, e.column, e.message)))
     }
 }
 
+/// Pretty-prints the question in the canonical source format, so that parsing the output gives back the same question.
 impl fmt::Display for Question {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut body = String::new();
+
         for (q, a) in &self.dat {
             if let Some(s) = q {
-                write!(f, "{s}")?;
+                body.push_str(s);
             }
             if let Some(a) = a {
-                write!(f, "{a}")?;
+                body.push_str(&a.to_string());
             }
         }
 
-        for (idx, pool) in self.pools.iter().enumerate() {
-            write!(f, ". Set #{}: ", idx + 1)?;
+        write!(f, "{}", body.trim())?;
+
+        for pool in &self.pools {
+            write!(f, "; ")?;
             for (item_idx, item) in pool.iter().enumerate() {
                 if item_idx > 0 {
-                    write!(f, ",")?;
+                    write!(f, ", ")?;
                 }
-                write!(f, " {item}")?;
+                write!(f, "{}", item.trim())?;
             }
         }
         Ok(())
@@ -285,58 +406,130 @@ impl fmt::Display for Question {
 
 impl Question {
     pub fn check_answers(&self, answers: Vec<String>) -> Option<String> {
+        if self.grade(&answers).into_iter().all(|correct| correct) {
+            None
+        } else {
+            Some(self.correction())
+        }
+    }
 
-        let mut used_from_pools = vec![Vec::new(); self.pools.len()];
-        let mut all_correct = true;
+    /// Grades each answer on its own, in the same order as the blanks. Blanks without an answer aren't graded.
+    pub fn grade(&self, answers: &[String]) -> Vec<bool> {
 
-        for (expected, provided) in self.dat.iter()
-                                            .filter_map(|(_, ans)| ans.as_ref())
-                                            .zip(answers.iter()) {
+        let mut used_from_pools = vec![Vec::new(); self.pools.len()];
 
-            let correct = match expected {
-                Answer::Raw(raw) => raw.trim().to_lowercase() == provided.trim().to_lowercase(),
+        self.dat.iter()
+                .filter_map(|(_, ans)| ans.as_ref())
+                .zip(answers.iter())
+                .map(|(expected, provided)| match expected {
+            Answer::Raw(raw) => raw.trim().to_lowercase() == provided.trim().to_lowercase(),
 
-                Answer::SharedPool(pool_idx) => {
+            Answer::SharedPool(pool_idx) => {
 
-                    let pool = self.pools.get(*pool_idx)
-                                         .expect("Indexes to shared pools should have been checked when question was constructed!");
+                let pool = self.pools.get(*pool_idx)
+                                     .expect("Indexes to shared pools should have been checked when question was constructed!");
 
-                    let mut res = false;
-                    for (option_idx, option) in pool.iter().enumerate() {
-                        let used = used_from_pools.get_mut(*pool_idx).unwrap();
+                let mut res = false;
+                for (option_idx, option) in pool.iter().enumerate() {
+                    let used = used_from_pools.get_mut(*pool_idx).unwrap();
 
-                        if (used.is_empty()
-                            || !used.contains(&option_idx))
-                            && provided.trim().to_lowercase() == option.trim().to_lowercase() {
+                    if (used.is_empty()
+                        || !used.contains(&option_idx))
+                        && provided.trim().to_lowercase() == option.trim().to_lowercase() {
 
-                            used.push(option_idx);
-                            res = true;
-                            break;
-                        }
+                        used.push(option_idx);
+                        res = true;
+                        break;
                     }
-                    res
-                },
+                }
+                res
+            },
 
-                Answer::OneOf(options) => {
-                    options.iter().any(|opt| opt.trim().to_lowercase() == provided.trim().to_lowercase())
-                },
-            };
+            Answer::OneOf(options) => {
+                options.iter().any(|opt| opt.trim().to_lowercase() == provided.trim().to_lowercase())
+            },
+        }).collect()
+    }
+
+    /// What each blank accepts, in the same order as the blanks. A blank from a pool accepts anything in that pool.
+    pub fn expected(&self) -> Vec<String> {
+        self.dat
+            .iter()
+            .filter_map(|(_, answer)| answer.as_ref())
+            .map(|answer| match answer {
+                Answer::SharedPool(idx) => self.pools[*idx].iter().map(|item| item.trim()).collect::<Vec<_>>().join(" OR "),
+                answer => answer.describe(),
+            })
+            .collect()
+    }
+
+    /// The question with all of its blanks filled in, shown when the answers were wrong.
+    pub fn correction(&self) -> String {
+        let mut s = String::new();
+
+        for (q, a) in &self.dat {
+            if let Some(q) = q {
+                s.push_str(q);
+            }
+            if let Some(a) = a {
+                s.push_str(&a.describe());
+            }
+        }
 
-            if !correct {
-                all_correct = false;
+        for (idx, pool) in self.pools.iter().enumerate() {
+            s.push_str(&format!(". Set #{}: ", idx + 1));
+            for (item_idx, item) in pool.iter().enumerate() {
+                if item_idx > 0 {
+                    s.push(',');
+                }
+                s.push_str(&format!(" {item}"));
             }
         }
 
-        if all_correct {
-            None
-        } else {
-            Some(format!("{self}"))
+        s
+    }
+
+    /// Shuffles the alternatives of every answer and the items in every pool, which changes the order they're shown
+    /// in without changing what counts as correct.
+    pub fn shuffle_choices(&mut self, rng: &mut Rng) {
+        for (_, answer) in &mut self.dat {
+            if let Some(Answer::OneOf(options)) = answer {
+                rng.shuffle(options);
+            }
         }
+        for pool in &mut self.pools {
+            rng.shuffle(pool);
+        }
+    }
+
+    /// The question the way renderers show it, leaving the answers out.
+    pub fn view(&self) -> QuestionView {
+        let mut parts = Vec::new();
+
+        for (text, answer) in &self.dat {
+            if let Some(text) = text {
+                parts.push(Part::Text(text.clone()));
+            }
+            if let Some(answer) = answer {
+                let kind = match answer {
+                    Answer::Raw(_) => BlankKind::FreeText,
+                    Answer::OneOf(_) => BlankKind::OneOf,
+                    Answer::SharedPool(idx) => BlankKind::Pool(*idx),
+                };
+                parts.push(Part::Blank(BlankView { kind }));
+            }
+        }
+
+        QuestionView { parts, pools: self.pools.clone() }
     }
 
-    pub fn renderable(&self) -> impl Iterator<Item=(Option<&str>, bool)> {
+    /// The question the way it's asked, with every blank shown as `___`.
+    pub fn prompt(&self) -> String {
         self.dat
             .iter()
-            .map(|(q, ans)| (q.as_ref().map(|s| s.as_str()), ans.is_some()))
+            .map(|(text, answer)| format!("{}{}", text.as_deref().unwrap_or(""), if answer.is_some() { "___" } else { "" }))
+            .collect::<String>()
+            .trim()
+            .to_string()
     }
 }
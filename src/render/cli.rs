@@ -1,59 +1,93 @@
-use super::Render;
+use super::{Event, Prompt, Render};
+use crate::summary::Summary;
 
 use std::{process, io::{self, BufRead, Write}, string};
 
 pub struct Cli {
-    stdin: io::StdinLock<'static>
+    stdin: io::StdinLock<'static>,
+    /// Whether the prompt shown last was a question.
+    answering: bool,
+    /// Whether all of a question's blanks are answered on a single line, split up by commas, rather than one at a time.
+    one_line: bool,
+}
+
+impl Cli {
+    pub fn one_line(mut self, one_line: bool) -> Self {
+        self.one_line = one_line;
+        self
+    }
+}
+
+/// What's printed for `prompt`, before the learner types in their line.
+pub fn prompt_text(prompt: &Prompt, one_line: bool) -> String {
+    match prompt {
+        Prompt::Blanks { question, answers, cursor } => {
+            // The blank being answered is put in brackets, unless they're all answered at once
+            let current = (!one_line).then_some(cursor.blank);
+            format!("{}: ", question.fill_in(answers, current))
+        }
+        Prompt::Correction { .. } => String::from("Please type that out: "),
+        Prompt::Rating => String::from("How hard was that to remember? [1] Hard [2] Good [3] Easy: "),
+    }
+}
+
+/// What a line typed in at a prompt means. When all of a question's blanks are answered at once, commas split it up.
+pub fn line_event(line: &str, answering: bool, one_line: bool) -> Event {
+    if answering && one_line {
+        Event::Answers(line.trim().split(',').map(string::ToString::to_string).collect())
+    } else {
+        Event::Line(line.trim().to_string())
+    }
 }
 
 impl Render for Cli {
-    fn ask<'a, I>(&mut self, question: I) -> Result<Vec<String>, String>
-        where I: std::iter::Iterator<Item = (Option<&'a str>, bool)> {
-
-        let to_display = question.map(|(q, ans)| {
-            let mut s = None;
-            if let Some(q) = q {
-                s = Some(q.to_string());
-            }
-            if ans {
-                s.get_or_insert(String::new()).push_str("___");
-            }
-            s.expect("Renderer should never be given an item without a question or an answer")
-        }).collect::<String>();
-
-        assert_ne!(to_display.trim(), "");
-
-        #[cfg(windows)]
-        process::Command::new("cls").output().expect("Could not clear screen");
-        #[cfg(not(windows))]
-        process::Command::new("clear").output().expect("Could not clear screen");
-
-        print!("{to_display}: ");
+    fn show_prompt(&mut self, prompt: &Prompt) {
+        self.answering = matches!(prompt, Prompt::Blanks { .. });
+
+        if self.answering {
+            #[cfg(windows)]
+            process::Command::new("cls").output().expect("Could not clear screen");
+            #[cfg(not(windows))]
+            process::Command::new("clear").output().expect("Could not clear screen");
+        }
+
+        print!("{}", prompt_text(prompt, self.one_line));
         io::stdout().flush().expect("Could not flush stdout!");
+    }
 
+    fn next_event(&mut self) -> Result<Event, String> {
         let mut buf = String::new();
 
-        self.stdin.read_line(&mut buf).expect("Could not read from standard input!");
+        let read = self.stdin.read_line(&mut buf).map_err(|e| format!("Could not read from standard input: {e}!"))?;
+        if read == 0 {
+            return Err(String::from("Standard input ended before the session did!"));
+        }
 
-        Ok(buf.trim().split(',').map(string::ToString::to_string).collect())
+        Ok(line_event(&buf, self.answering, self.one_line))
     }
 
-    fn show_result(&mut self, correction: Option<String>) {
+    fn show_result(&mut self, _grades: &[bool], correction: Option<&str>) {
         if let Some(correction) = correction {
             println!("INCORRECT! The correct answer is `{correction}`!");
-            loop {
-                print!("Please type that out: ");
-                io::stdout().flush().expect("Could not flush stdout!");
+        }
+    }
 
-                let mut buf = String::new();
+    fn show_report(&mut self, report: &str) {
+        println!("{report}");
+    }
 
-                self.stdin.read_line(&mut buf).expect("Could not read from standard input!");
+    fn show_summary(&mut self, summary: &Summary) {
+        println!();
+        println!("SESSION SUMMARY");
+        print!("{summary}");
+    }
 
-                if correction.trim().to_lowercase() == buf.trim().to_lowercase() {
-                    break;
-                }
-            }
-        }
+    fn display_error(&mut self, err: &str) {
+        eprintln!("ERROR: {err}");
+    }
+
+    fn display_warning(&mut self, warning: &str) {
+        println!("WARNING: {warning}");
     }
 
     fn finish(&mut self) -> Result<(), String> {
@@ -65,7 +99,9 @@ impl Render for Cli {
     fn init() -> Result<Self, String> {
         let stdin = io::stdin();
         Ok(Self {
-            stdin: stdin.lock() // If you get errors from this, you need to update your rust installation
+            stdin: stdin.lock(), // If you get errors from this, you need to update your rust installation
+            answering: false,
+            one_line: false,
         })
     }
 }
@@ -1,25 +1,214 @@
+#[cfg(any(feature = "tui", feature = "crossterm-tui"))]
+pub mod screen;
 #[cfg(feature = "tui")]
 mod tui;
-mod cli;
+#[cfg(all(feature = "crossterm-tui", not(feature = "tui")))]
+mod term;
+#[cfg(not(any(feature = "tui", feature = "crossterm-tui")))]
+pub mod cli;
+#[cfg(test)]
+pub mod script;
 
 #[cfg(feature = "tui")]
 pub use tui::NCurses;
+#[cfg(all(feature = "crossterm-tui", not(feature = "tui")))]
+pub use term::Crossterm;
+#[cfg(not(any(feature = "tui", feature = "crossterm-tui")))]
 pub use cli::Cli;
 
-use std::iter;
+use std::time::{Duration, Instant};
 
+use crate::summary::Summary;
+
+/// How long the learner took on a single blank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    /// From when the blank was reached until the first key was pressed in it.
+    pub first_key: Duration,
+    /// From when the blank was reached until it was finished.
+    pub total: Duration,
+}
+
+/// Everything the learner typed in for a question, and how long it took them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub answers: Vec<String>,
+    /// One for each blank, if the renderer can tell the blanks apart while they're being typed in.
+    pub timings: Vec<Timing>,
+    /// How long the whole question took to answer.
+    pub total: Duration,
+}
+
+impl Response {
+    /// How long the slowest blank took, or the average per blank if they weren't timed separately.
+    pub fn slowest_blank(&self) -> Duration {
+        match self.timings.iter().map(|t| t.total).max() {
+            Some(slowest) => slowest,
+            None => self.total / self.answers.len().max(1) as u32,
+        }
+    }
+}
+
+/// Where the session is at, for renderers that can show it alongside the question.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    pub file: String,
+    pub section: Option<String>,
+    /// Which question this is, not counting retries, out of how many there are.
+    pub position: usize,
+    pub total: usize,
+    /// How many answers have been right so far, out of how many. Exams don't tell until the end.
+    pub score: Option<(usize, usize)>,
+    /// How many missed questions are waiting to be asked again.
+    pub retries: usize,
+    pub started: Instant,
+    pub time_limit: Option<Duration>,
+}
+
+/// A question the way it's shown to the learner, without its answers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionView {
+    pub parts: Vec<Part>,
+    /// The sets that `{n}` blanks take their answers from, in the order they're shown in.
+    pub pools: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Part {
+    Text(String),
+    Blank(BlankView),
+}
+
+/// Everything a renderer may know about a blank before it's answered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlankView {
+    pub kind: BlankKind,
+}
+
+/// What sort of answer a blank takes, so that a renderer can offer the right way of giving it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlankKind {
+    /// Anything typed in.
+    FreeText,
+    /// Any one of a few alternatives. They're all right, so they aren't shown.
+    OneOf,
+    /// An item from the set at this index in `QuestionView::pools`, which no other blank in the question may use.
+    Pool(usize),
+}
+
+impl QuestionView {
+    pub fn blanks(&self) -> impl Iterator<Item = &BlankView> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Blank(blank) => Some(blank),
+            Part::Text(_) => None,
+        })
+    }
+
+    /// Writes out the question with its blanks filled in with `answers`, putting the one being answered in brackets.
+    // The full screen renderers style each part themselves, so only plain text renderers need this
+    #[cfg(any(test, not(any(feature = "tui", feature = "crossterm-tui"))))]
+    pub fn fill_in(&self, answers: &[String], current: Option<usize>) -> String {
+        let mut filled = String::new();
+        let mut blank = 0;
+
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => filled.push_str(text),
+                Part::Blank(_) => {
+                    let answer = match answers[blank].as_str() {
+                        "" => "___",
+                        answer => answer,
+                    };
+                    if current == Some(blank) {
+                        filled.push_str(&format!("[{answer}]"));
+                    } else {
+                        filled.push_str(answer);
+                    }
+                    blank += 1;
+                }
+            }
+        }
+
+        filled.trim().to_string()
+    }
+}
+
+/// Where the learner is typing while answering a question.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    pub blank: usize,
+    /// How many characters into the blank's answer the cursor is.
+    pub at: usize,
+}
+
+/// What the learner is being asked for, along with whatever they've entered so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt<'a> {
+    /// Filling in the blanks of a question, with one answer for each blank.
+    Blanks { question: &'a QuestionView, answers: &'a [String], cursor: Cursor },
+    /// Typing out the correct answer after getting a question wrong.
+    Correction { correction: &'a str, typed: &'a str },
+    /// Saying how hard a correctly answered question was to remember.
+    Rating,
+}
+
+/// Something the learner did at a prompt.
+// Only one renderer is built at a time, and none of them can send every kind of event
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Char(char),
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    PreviousBlank,
+    NextBlank,
+    /// Finishes the current blank, or whatever else is being typed.
+    Enter,
+    /// A whole line typed in at once, by renderers that can only read lines. It takes the place of whatever was typed
+    /// so far, and is finished straight away.
+    Line(String),
+    /// Answers for the current blank and the ones after it, typed in at once, which submit the question.
+    Answers(Vec<String>),
+    /// Stops the session early. What was answered so far still counts, and the summary is shown as usual.
+    Quit,
+}
+
+/// Shows the session to the learner and reports what they do. Renderers never decide what a key press means: they
+/// show a `Prompt` and hand back the next `Event`, and `input` takes care of the rest.
 pub trait Render: Sized {
-    fn ask<'a, I>(&mut self, question: I) -> Result<Vec<String>, String>
-        where I: iter::Iterator<Item = (Option<&'a str>, bool)>;
+    /// Shows `prompt`, in place of the one that was shown before. It's shown again after every event.
+    fn show_prompt(&mut self, prompt: &Prompt);
+
+    /// Waits for the learner to do something about the prompt that was shown last.
+    fn next_event(&mut self) -> Result<Event, String>;
+
+    /// Shows the answers a question was submitted with, before they're graded.
+    fn show_answers(&mut self, _question: &QuestionView, _answers: &[String]) {}
+
+    /// Shows whether the answers were right, along with the correct answer if they weren't. `grades` says whether each
+    /// blank was right on its own, in the same order as the blanks.
+    fn show_result(&mut self, grades: &[bool], correction: Option<&str>);
 
-    fn show_result(&mut self, correction: Option<String>);
+    /// Called before each question is asked.
+    fn show_status(&mut self, _status: &Status) {}
+
+    /// Shows the report at the end of an exam.
+    fn show_report(&mut self, report: &str);
+
+    /// Shows the statistics for the session that just ended.
+    fn show_summary(&mut self, summary: &Summary);
 
     fn init() -> Result<Self, String>;
 
     fn display_error(&mut self, _err: &str) {}
 
+    fn display_warning(&mut self, _warning: &str) {}
+
     fn finish(&mut self) -> Result<(), String> {
         Ok(())
     }
 }
-
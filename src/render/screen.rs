@@ -0,0 +1,462 @@
+//! Everything the full screen renderers have in common, whichever library they draw with.
+//!
+//! The screen is split in three: a status line at the top, the history of the session so far, which can be scrolled
+//! through, and at the bottom the input area, which holds whatever is being answered right now. `Screen` keeps track
+//! of all of that and of the keys being pressed, and a `Backend` only has to draw it and read keys from the terminal.
+
+use std::{collections::VecDeque, time};
+
+use super::{Event, Part, Prompt, QuestionView, Status};
+use crate::{summary::Summary, theme::{Paint, Theme}, Render};
+
+/// How many lines of the session are kept around to scroll back through.
+const HISTORY_LINES: usize = 1000;
+/// How many lines the input area at the bottom of the screen gets, unless the terminal is very short.
+const INPUT_LINES: usize = 6;
+
+/// A piece of text and how it should look.
+pub type Span = (Paint, String);
+
+/// A key press, or something else happening at the terminal, as far as the renderer cares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Char(char),
+    Enter,
+    Tab,
+    BackTab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    /// The terminal changed size, so everything has to be laid out again.
+    Resize,
+    /// A second went by without any key being pressed.
+    Tick,
+    /// Any other key, like the function keys.
+    Other,
+    /// The learner wants to stop the session.
+    // ncurses leaves Ctrl-C to the terminal, so only crossterm sends this
+    #[allow(dead_code)]
+    Quit,
+}
+
+/// How many rows each part of the screen gets. The status line is always the top row, and the input area starts with
+/// a line separating it from the history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    pub cols: usize,
+    pub history: usize,
+    pub input: usize,
+}
+
+impl Layout {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        let input = INPUT_LINES.min(rows / 2).max(1);
+        Self { cols, history: rows.saturating_sub(input + 2).max(1), input }
+    }
+}
+
+/// Draws on and reads keys from the terminal, for `Screen`.
+pub trait Backend: Sized {
+    /// Takes over the terminal.
+    fn open(theme: &Theme) -> Result<Self, String>;
+
+    /// The size of the terminal, in rows and columns.
+    fn size(&self) -> (usize, usize);
+
+    /// Called when the backend is first opened, and again whenever the terminal changes size.
+    fn layout(&mut self, layout: &Layout);
+
+    fn draw_header(&mut self, line: &str);
+
+    /// Draws the lines of the history that are in view, oldest first.
+    fn draw_history(&mut self, lines: &[Vec<Span>]);
+
+    /// Draws the input area, which always comes last, so it's where the cursor is left. `notice` goes in the line
+    /// above it, and `cursor` is counted from the first of `lines`.
+    fn draw_input(&mut self, notice: Option<&str>, lines: &[Vec<Span>], cursor: Option<(usize, usize)>);
+
+    /// Waits for a key press, but no longer than a second, so that the clock in the status line keeps going.
+    fn read(&mut self) -> Input;
+
+    /// Hands the terminal back.
+    fn close(&mut self);
+}
+
+/// Breaks `text` into lines of at most `width` characters, on top of the line breaks it already has.
+pub fn wrap(text: &[Span], width: usize) -> Vec<Vec<Span>> {
+    let width = width.max(1);
+    let mut lines: Vec<Vec<Span>> = vec![Vec::new()];
+    let mut col = 0;
+
+    for (paint, part) in text {
+        for ch in part.chars() {
+            if ch == '\n' || col == width {
+                lines.push(Vec::new());
+                col = 0;
+            }
+            if ch == '\n' {
+                continue;
+            }
+
+            let line = lines.last_mut().expect("There's always a line");
+            match line.last_mut() {
+                Some((last, part)) if last == paint => part.push(ch),
+                _ => line.push((*paint, ch.to_string())),
+            }
+            col += 1;
+        }
+    }
+
+    lines
+}
+
+/// Where the `at`th character of `text` ends up once it's wrapped to `width`, as a row and a column.
+fn locate(text: &[Span], at: usize, width: usize) -> (usize, usize) {
+    let width = width.max(1);
+    let (mut row, mut col) = (0, 0);
+
+    for ch in text.iter().flat_map(|(_, part)| part.chars()).take(at) {
+        if ch == '\n' || col == width {
+            row += 1;
+            col = 0;
+        }
+        if ch != '\n' {
+            col += 1;
+        }
+    }
+
+    // A full line pushes the cursor onto the start of the next one
+    if col == width {
+        (row + 1, 0)
+    } else {
+        (row, col)
+    }
+}
+
+/// Fills the blanks of a question in with whatever has been typed into them so far, showing `___` for the empty ones,
+/// except for the blank being edited. `focus` is that blank and how many characters into it the cursor is, and the
+/// cursor's position in the whole question is returned with it.
+pub fn fill_in(question: &QuestionView, answers: &[String], focus: Option<(usize, usize)>) -> (Vec<Span>, Option<usize>) {
+    let mut filled = Vec::new();
+    let mut cursor = None;
+    let mut pos = 0;
+
+    for part in &question.parts {
+        if let Part::Text(text) = part {
+            filled.push((Paint::Plain, text.clone()));
+            continue;
+        }
+
+        let answer = &answers[pos];
+
+        match focus {
+            Some((blank, at)) if blank == pos => {
+                let before = filled.iter().map(|(_, text): &Span| text.chars().count()).sum::<usize>();
+                cursor = Some(before + at.min(answer.chars().count()));
+                filled.push((Paint::Active, answer.clone()));
+            }
+            _ if answer.is_empty() => filled.push((Paint::Blank, "___".to_string())),
+            _ => filled.push((Paint::Blank, answer.clone())),
+        }
+        pos += 1;
+    }
+
+    filled.retain(|(_, text)| !text.is_empty());
+    (filled, cursor)
+}
+
+/// Colours each blank in `answered`, as written out by `fill_in`, by whether it was right.
+pub fn paint_grades(answered: &[Span], grades: &[bool]) -> Vec<Span> {
+    let mut grades = grades.iter();
+    answered
+        .iter()
+        .map(|(paint, text)| match paint {
+            Paint::Blank if grades.next() == Some(&true) => (Paint::Correct, text.clone()),
+            Paint::Blank => (Paint::Wrong, text.clone()),
+            _ => (*paint, text.clone()),
+        })
+        .collect()
+}
+
+/// Formats `duration` as minutes and seconds, like `4:05`.
+fn clock(duration: time::Duration) -> String {
+    let secs = duration.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// The text of the status line, cut down to fit in `width` characters.
+pub fn status_line(status: &Status, elapsed: time::Duration, width: usize) -> String {
+    let place = match &status.section {
+        Some(section) => format!(" {} › {section}", status.file),
+        None => format!(" {}", status.file),
+    };
+
+    let mut progress = format!("Question {}/{}", status.position, status.total);
+    if let Some((correct, answered)) = status.score {
+        progress.push_str(&format!("  Score {correct}/{answered}"));
+    }
+    if status.retries > 0 {
+        progress.push_str(&format!("  Retrying {}", status.retries));
+    }
+    progress.push_str(&format!("  {}", clock(elapsed)));
+    if let Some(limit) = status.time_limit {
+        progress.push_str(&format!(" ({} left)", clock(limit.saturating_sub(elapsed))));
+    }
+    progress.push(' ');
+
+    // The file name gives way first when there isn't room for everything, but always keeps a space after it
+    let room = width.saturating_sub(progress.chars().count());
+    let place = match place.chars().count() {
+        len if len < room => place,
+        _ => place.chars().take(room.saturating_sub(1)).collect(),
+    };
+    let gap = room.saturating_sub(place.chars().count());
+
+    format!("{place}{}{progress}", " ".repeat(gap)).chars().take(width).collect()
+}
+
+/// A full screen renderer, drawn by `B`.
+pub struct Screen<B: Backend> {
+    backend: B,
+    layout: Layout,
+    /// One entry per line, oldest first.
+    history: VecDeque<Vec<Span>>,
+    /// How many lines up from the newest one the history is scrolled.
+    scroll: usize,
+    input: Vec<Span>,
+    /// How many characters into `input` the cursor is, if it's shown at all.
+    cursor: Option<usize>,
+    status: Option<Status>,
+    /// The question answered last, with a span for each blank, so that they can be coloured once they're graded.
+    answered: Vec<Span>,
+}
+
+impl<B: Backend> Drop for Screen<B> {
+    fn drop(&mut self) {
+        self.finish().unwrap()
+    }
+}
+
+impl<B: Backend> Screen<B> {
+    fn relayout(&mut self) {
+        let (rows, cols) = self.backend.size();
+        self.layout = Layout::new(rows, cols);
+        self.backend.layout(&self.layout);
+        self.redraw();
+    }
+
+    fn redraw(&mut self) {
+        self.draw_header();
+        self.draw_history();
+        self.draw_input();
+    }
+
+    fn draw_header(&mut self) {
+        let line = match &self.status {
+            Some(status) => status_line(status, status.started.elapsed(), self.layout.cols),
+            None => String::new(),
+        };
+        self.backend.draw_header(&line);
+    }
+
+    fn draw_history(&mut self) {
+        let rows = self.layout.history;
+        let lines = self.history.iter().flat_map(|line| wrap(line, self.layout.cols)).collect::<Vec<_>>();
+
+        self.scroll = self.scroll.min(lines.len().saturating_sub(rows));
+        let end = lines.len() - self.scroll;
+        let start = end.saturating_sub(rows);
+
+        self.backend.draw_history(&lines[start..end]);
+    }
+
+    /// Only the input area changes while typing, so this is all that gets drawn for each key press.
+    fn draw_input(&mut self) {
+        let rows = self.layout.input;
+        let lines = wrap(&self.input, self.layout.cols);
+        let cursor = self.cursor.map(|at| locate(&self.input, at, self.layout.cols));
+        // If it doesn't all fit, keep the cursor in view
+        let first = cursor.map_or(lines.len(), |(row, _)| row + 1).saturating_sub(rows);
+        let end = lines.len().min(first + rows);
+
+        let notice = (self.scroll > 0).then(|| format!(" {} newer line(s), Page Down to see them ", self.scroll));
+        let cursor = cursor.map(|(row, col)| (row - first, col));
+
+        self.backend.draw_input(notice.as_deref(), &lines[first..end], cursor);
+    }
+
+    /// Adds `text` to the bottom of the history, scrolling back down to it.
+    fn push_history(&mut self, text: Vec<Span>) {
+        let mut lines = wrap(&text, usize::MAX);
+        while lines.len() > 1 && lines.last().is_some_and(Vec::is_empty) {
+            lines.pop();
+        }
+
+        self.history.extend(lines);
+        while self.history.len() > HISTORY_LINES {
+            self.history.pop_front();
+        }
+
+        self.scroll = 0;
+        self.redraw();
+    }
+
+    fn set_input(&mut self, text: Vec<Span>, cursor: Option<usize>) {
+        self.input = text;
+        self.cursor = cursor;
+        self.draw_input();
+    }
+
+    /// Waits for the next key press, taking care of scrolling and resizing along the way.
+    fn next_input(&mut self) -> Input {
+        loop {
+            let page = self.layout.history.saturating_sub(1).max(1);
+
+            match self.backend.read() {
+                Input::Resize => self.relayout(),
+                Input::Tick => {
+                    self.draw_header();
+                    self.draw_input();
+                }
+                Input::PageUp => {
+                    self.scroll += page;
+                    self.redraw();
+                }
+                Input::PageDown => {
+                    self.scroll = self.scroll.saturating_sub(page);
+                    self.redraw();
+                }
+                input => return input,
+            }
+        }
+    }
+}
+
+impl<B: Backend> Render for Screen<B> {
+    fn show_prompt(&mut self, prompt: &Prompt) {
+        match prompt {
+            Prompt::Blanks { question, answers, cursor } => {
+                let (text, at) = fill_in(question, answers, Some((cursor.blank, cursor.at)));
+                self.set_input(text, at);
+            }
+            Prompt::Correction { typed, .. } => {
+                let typing = format!("Please type that out: {typed}");
+                let len = typing.chars().count();
+                self.set_input(vec![(Paint::Plain, typing)], Some(len));
+            }
+            Prompt::Rating => {
+                let prompt = "How hard was that to remember? [1] Hard [2] Good [3] Easy: ";
+                self.set_input(vec![(Paint::Plain, prompt.to_string())], Some(prompt.chars().count()));
+            }
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Event, String> {
+        loop {
+            // Scrolling and resizing are taken care of by `next_input`
+            let event = match self.next_input() {
+                Input::Char(ch) => Event::Char(ch),
+                Input::Enter => Event::Enter,
+                Input::Backspace => Event::Backspace,
+                Input::Delete => Event::Delete,
+                Input::Left => Event::Left,
+                Input::Right => Event::Right,
+                Input::Home => Event::Home,
+                Input::End => Event::End,
+                Input::Up | Input::BackTab => Event::PreviousBlank,
+                Input::Down | Input::Tab => Event::NextBlank,
+                Input::Quit => Event::Quit,
+                Input::PageUp | Input::PageDown | Input::Resize | Input::Tick | Input::Other => continue,
+            };
+            return Ok(event);
+        }
+    }
+
+    fn show_answers(&mut self, question: &QuestionView, answers: &[String]) {
+        let (answered, _) = fill_in(question, answers, None);
+        self.answered = answered.clone();
+        self.push_history(answered);
+        self.set_input(Vec::new(), None);
+    }
+
+    fn init() -> Result<Self, String> {
+        let theme = match Theme::default_path() {
+            Some(path) => Theme::load(&path)?,
+            None => Theme::default(),
+        };
+
+        let backend = B::open(&theme)?;
+        let (rows, cols) = backend.size();
+
+        let mut screen = Self {
+            backend,
+            layout: Layout::new(rows, cols),
+            history: VecDeque::new(),
+            scroll: 0,
+            input: Vec::new(),
+            cursor: None,
+            status: None,
+            answered: Vec::new(),
+        };
+        screen.relayout();
+
+        Ok(screen)
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        self.set_input(vec![(Paint::Plain, "PROGRAM FINISHED. PRESS ANY KEY TO EXIT".to_string())], None);
+
+        self.next_input(); // Pause so that the person can see the finish of the program
+
+        self.backend.close();
+
+        Ok(())
+    }
+
+    fn display_error(&mut self, err: &str) {
+        self.set_input(vec![(Paint::Error, format!("ERROR: {err}.")), (Paint::Plain, " Press any button to continue.".to_string())], None);
+        self.next_input();
+        self.set_input(Vec::new(), None);
+    }
+
+    fn show_report(&mut self, report: &str) {
+        self.push_history(vec![(Paint::Plain, report.to_string())]);
+    }
+
+    fn show_summary(&mut self, summary: &Summary) {
+        self.push_history(vec![(Paint::Plain, format!("\nSESSION SUMMARY\n{summary}"))]);
+    }
+
+    fn display_warning(&mut self, warning: &str) {
+        self.set_input(vec![(Paint::Error, format!("WARNING: {warning}.")), (Paint::Plain, " Press any button to continue.".to_string())], None);
+        self.next_input();
+        self.set_input(Vec::new(), None);
+    }
+
+    fn show_status(&mut self, status: &Status) {
+        self.status = Some(status.clone());
+        self.draw_header();
+        self.draw_input();
+    }
+
+    fn show_result(&mut self, grades: &[bool], correction: Option<&str>) {
+        // The question that was just answered is the last thing in the history. It's painted again from the spans it
+        // was written with, since blanks next to each other end up in the same span once it's in the history.
+        let painted = paint_grades(&self.answered, grades);
+        if let (Some(line), Some(painted)) = (self.history.back_mut(), wrap(&painted, usize::MAX).pop()) {
+            *line = painted;
+        }
+        self.redraw();
+
+        if let Some(correction) = correction {
+            self.push_history(vec![(Paint::Wrong, format!("INCORRECT. The correct answer is: \"{correction}\"."))]);
+        }
+    }
+}
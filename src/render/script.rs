@@ -0,0 +1,92 @@
+//! A renderer without a terminal, for testing whole sessions. It plays back a script of what the learner does and
+//! writes down everything it's asked to show, one entry per thing, so that tests can check the lot afterwards.
+
+use std::collections::VecDeque;
+
+use super::{Event, Prompt, QuestionView, Render, Status};
+use crate::summary::Summary;
+
+pub struct Script {
+    events: VecDeque<Event>,
+    /// Everything that's been shown, oldest first.
+    pub shown: Vec<String>,
+}
+
+impl Script {
+    pub fn new(events: impl IntoIterator<Item = Event>) -> Self {
+        Self { events: events.into_iter().collect(), shown: Vec::new() }
+    }
+
+    /// A script where every blank, correction and rating is typed in as a whole line.
+    pub fn lines(lines: &[&str]) -> Self {
+        Self::new(lines.iter().map(|line| Event::Line(line.to_string())))
+    }
+
+    /// Whether every event in the script has been used up.
+    pub fn finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl Render for Script {
+    fn show_prompt(&mut self, prompt: &Prompt) {
+        let shown = match prompt {
+            Prompt::Blanks { question, answers, cursor } => format!("Ask: {}", question.fill_in(answers, Some(cursor.blank))),
+            Prompt::Correction { correction, typed: "" } => format!("Type out: {correction}"),
+            Prompt::Correction { correction, typed } => format!("Type out: {correction} | {typed}"),
+            Prompt::Rating => String::from("Rate recall"),
+        };
+        self.shown.push(shown);
+    }
+
+    fn next_event(&mut self) -> Result<Event, String> {
+        let last = self.shown.last().map_or("", String::as_str);
+        self.events.pop_front().ok_or_else(|| format!("The script ran out at `{last}`!"))
+    }
+
+    fn show_answers(&mut self, question: &QuestionView, answers: &[String]) {
+        self.shown.push(format!("Answered: {}", question.fill_in(answers, None)));
+    }
+
+    fn show_result(&mut self, _grades: &[bool], correction: Option<&str>) {
+        match correction {
+            Some(correction) => self.shown.push(format!("Incorrect: {correction}")),
+            None => self.shown.push(String::from("Correct")),
+        }
+    }
+
+    fn show_status(&mut self, status: &Status) {
+        let place = match &status.section {
+            Some(section) => format!("{} › {section}", status.file),
+            None => status.file.clone(),
+        };
+        let score = match status.score {
+            Some((correct, answered)) => format!(", score {correct}/{answered}"),
+            None => String::new(),
+        };
+        self.shown.push(format!(
+            "Status: {place}, question {}/{}{score}, retrying {}",
+            status.position, status.total, status.retries
+        ));
+    }
+
+    fn show_report(&mut self, report: &str) {
+        self.shown.push(format!("Report: {report}"));
+    }
+
+    fn show_summary(&mut self, summary: &Summary) {
+        self.shown.push(format!("Summary: {summary}"));
+    }
+
+    fn init() -> Result<Self, String> {
+        Ok(Self::new([]))
+    }
+
+    fn display_error(&mut self, err: &str) {
+        self.shown.push(format!("Error: {err}"));
+    }
+
+    fn display_warning(&mut self, warning: &str) {
+        self.shown.push(format!("Warning: {warning}"));
+    }
+}
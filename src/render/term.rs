@@ -0,0 +1,178 @@
+use std::{env, io::{self, Write}, time::Duration};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    queue,
+    style::{self, Attribute, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use super::screen::{Backend, Input, Layout, Screen, Span};
+use crate::theme::{Color, Paint, Theme};
+
+pub type Crossterm = Screen<Term>;
+
+/// Draws the screen with crossterm, which needs nothing but Rust.
+pub struct Term {
+    out: io::Stdout,
+    layout: Layout,
+    theme: Theme,
+    /// Whether to use the theme's colours, or just bold, underlined and reversed text.
+    colors: bool,
+}
+
+fn term_color(color: Color) -> style::Color {
+    match color {
+        Color::Default => style::Color::Reset,
+        Color::Black => style::Color::Black,
+        Color::Red => style::Color::DarkRed,
+        Color::Green => style::Color::DarkGreen,
+        Color::Yellow => style::Color::DarkYellow,
+        Color::Blue => style::Color::DarkBlue,
+        Color::Magenta => style::Color::DarkMagenta,
+        Color::Cyan => style::Color::DarkCyan,
+        Color::White => style::Color::Grey,
+    }
+}
+
+impl Term {
+    /// Draws `line` from the start of `row`, clearing whatever was left there before.
+    fn draw_line(&mut self, row: usize, line: &[Span]) -> io::Result<()> {
+        queue!(self.out, MoveTo(0, row as u16), Clear(ClearType::CurrentLine))?;
+
+        for (paint, text) in line {
+            match self.theme.style(*paint) {
+                Some(style) if self.colors => {
+                    queue!(self.out, SetForegroundColor(term_color(style.fg)), SetBackgroundColor(term_color(style.bg)))?;
+                }
+                _ => {
+                    let attrs: &[Attribute] = match paint {
+                        Paint::Blank => &[Attribute::Underlined],
+                        Paint::Active => &[Attribute::Reverse],
+                        Paint::Wrong => &[Attribute::Bold],
+                        Paint::Error => &[Attribute::Bold, Attribute::Reverse],
+                        Paint::Plain | Paint::Correct => &[],
+                    };
+                    for attr in attrs {
+                        queue!(self.out, SetAttribute(*attr))?;
+                    }
+                }
+            }
+
+            queue!(self.out, Print(text), ResetColor, SetAttribute(Attribute::Reset))?;
+        }
+
+        Ok(())
+    }
+
+    fn try_draw_input(&mut self, notice: Option<&str>, lines: &[Vec<Span>], cursor: Option<(usize, usize)>) -> io::Result<()> {
+        let top = self.layout.history + 1;
+        let cols = self.layout.cols;
+
+        let mut separator = "─".repeat(cols);
+        if let Some(notice) = notice {
+            separator = format!("──{notice}{}", "─".repeat(cols.saturating_sub(notice.chars().count() + 2)));
+        }
+        queue!(self.out, MoveTo(0, top as u16), Print(separator.chars().take(cols).collect::<String>()))?;
+
+        for row in 0..self.layout.input {
+            self.draw_line(top + 1 + row, lines.get(row).map_or(&[], Vec::as_slice))?;
+        }
+
+        match cursor {
+            Some((row, col)) => queue!(self.out, MoveTo(col as u16, (top + 1 + row) as u16), Show)?,
+            None => queue!(self.out, Hide)?,
+        }
+
+        self.out.flush()
+    }
+}
+
+impl Backend for Term {
+    fn open(theme: &Theme) -> Result<Self, String> {
+        let mut out = io::stdout();
+
+        terminal::enable_raw_mode().map_err(|e| format!("Could not set up the terminal: {e}!"))?;
+        crossterm::execute!(out, EnterAlternateScreen).map_err(|e| format!("Could not set up the terminal: {e}!"))?;
+
+        Ok(Self {
+            out,
+            layout: Layout::new(0, 0),
+            theme: theme.clone(),
+            colors: env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && env::var("TERM").map_or(true, |t| t != "dumb"),
+        })
+    }
+
+    fn size(&self) -> (usize, usize) {
+        let (cols, rows) = terminal::size().unwrap_or((80, 24));
+        (rows as usize, cols as usize)
+    }
+
+    fn layout(&mut self, layout: &Layout) {
+        self.layout = *layout;
+        // Anything that goes wrong drawing will go wrong again the next time it's drawn, and get noticed then
+        let _ = queue!(self.out, Clear(ClearType::All));
+    }
+
+    fn draw_header(&mut self, line: &str) {
+        let cols = self.layout.cols;
+        let _ = queue!(self.out, MoveTo(0, 0), Clear(ClearType::CurrentLine));
+        if !line.is_empty() {
+            let _ = queue!(
+                self.out,
+                SetAttribute(Attribute::Reverse),
+                Print(format!("{line:<cols$}")),
+                SetAttribute(Attribute::Reset)
+            );
+        }
+    }
+
+    fn draw_history(&mut self, lines: &[Vec<Span>]) {
+        for row in 0..self.layout.history {
+            let _ = self.draw_line(1 + row, lines.get(row).map_or(&[], Vec::as_slice));
+        }
+    }
+
+    fn draw_input(&mut self, notice: Option<&str>, lines: &[Vec<Span>], cursor: Option<(usize, usize)>) {
+        let _ = self.try_draw_input(notice, lines, cursor);
+    }
+
+    fn read(&mut self) -> Input {
+        match event::poll(Duration::from_secs(1)) {
+            Ok(true) => {}
+            _ => return Input::Tick,
+        }
+
+        let key = match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => key,
+            Ok(Event::Resize(_, _)) => return Input::Resize,
+            _ => return Input::Other,
+        };
+
+        match key.code {
+            // The terminal doesn't turn Ctrl-C into a signal while it's in raw mode
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Input::Quit,
+            KeyCode::Char(ch) => Input::Char(ch),
+            KeyCode::Enter => Input::Enter,
+            KeyCode::Tab => Input::Tab,
+            KeyCode::BackTab => Input::BackTab,
+            KeyCode::Backspace => Input::Backspace,
+            KeyCode::Delete => Input::Delete,
+            KeyCode::Left => Input::Left,
+            KeyCode::Right => Input::Right,
+            KeyCode::Up => Input::Up,
+            KeyCode::Down => Input::Down,
+            KeyCode::Home => Input::Home,
+            KeyCode::End => Input::End,
+            KeyCode::PageUp => Input::PageUp,
+            KeyCode::PageDown => Input::PageDown,
+            _ => Input::Other,
+        }
+    }
+
+    fn close(&mut self) {
+        let _ = crossterm::execute!(self.out, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
@@ -1,182 +1,203 @@
-use std::{fmt, iter};
+use std::ptr;
 
-use crate::Render;
+use super::screen::{Backend, Input, Layout, Screen, Span};
+use crate::theme::{Color, Paint, Theme};
 
-pub struct NCurses {
-    screen: String
+pub type NCurses = Screen<Curses>;
+
+/// Draws the screen with ncurses, in a window for each part of it.
+pub struct Curses {
+    header_win: ncurses::WINDOW,
+    history_win: ncurses::WINDOW,
+    input_win: ncurses::WINDOW,
+    /// The attributes each of `Paint::STYLED` is drawn with.
+    attrs: [ncurses::attr_t; 5],
 }
 
-impl Drop for NCurses {
-    fn drop(&mut self) {
-        self.finish().unwrap()
-    }
+fn size(win: ncurses::WINDOW) -> (i32, i32) {
+    let (mut rows, mut cols) = (0, 0);
+    ncurses::getmaxyx(win, &mut rows, &mut cols);
+    (rows, cols)
 }
 
-impl NCurses {
-    pub fn get_char() -> char {
-        let ch = ncurses::get_wch().unwrap();
-        match ch {
-            ncurses::WchResult::Char(c) => char::from_u32(c).expect("Could not decode from input!"),
-            ncurses::WchResult::KeyCode(_) => todo!("Handle other key inputs!"),
-        }
+fn color_number(color: Color) -> i16 {
+    match color {
+        Color::Default => -1,
+        Color::Black => ncurses::COLOR_BLACK,
+        Color::Red => ncurses::COLOR_RED,
+        Color::Green => ncurses::COLOR_GREEN,
+        Color::Yellow => ncurses::COLOR_YELLOW,
+        Color::Blue => ncurses::COLOR_BLUE,
+        Color::Magenta => ncurses::COLOR_MAGENTA,
+        Color::Cyan => ncurses::COLOR_CYAN,
+        Color::White => ncurses::COLOR_WHITE,
     }
+}
 
-    fn print<T: fmt::Display>(dat: T) {
-        let buf = format!("{dat}");
-        ncurses::addstr(&buf);
+/// Sets up a colour pair for each part of the screen, or picks attributes that tell them apart without colour if the
+/// terminal can't show it or `NO_COLOR` is set.
+fn attributes(theme: &Theme) -> [ncurses::attr_t; 5] {
+    let colors = ncurses::has_colors() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty());
+
+    if !colors {
+        return Paint::STYLED.map(|paint| match paint {
+            Paint::Blank => ncurses::A_UNDERLINE(),
+            Paint::Active => ncurses::A_REVERSE(),
+            Paint::Wrong => ncurses::A_BOLD(),
+            Paint::Error => ncurses::A_BOLD() | ncurses::A_REVERSE(),
+            Paint::Plain | Paint::Correct => ncurses::A_NORMAL(),
+        });
     }
 
-    pub fn render_partially_answered<'a>(&self, answers: &[String], data: impl iter::Iterator<Item=&'a (Option<&'a str>, bool)>, current: &str) -> String {
-        ncurses::clear();
-        ncurses::addstr(&self.screen);
+    ncurses::start_color();
+    ncurses::use_default_colors();
 
-        let mut to_render = String::new();
-        let mut pos = 0;
+    let mut pair = 0;
+    Paint::STYLED.map(|paint| {
+        let style = theme.style(paint).expect("Styled paints always have a style");
+        pair += 1;
+        ncurses::init_pair(pair, color_number(style.fg), color_number(style.bg));
+        ncurses::COLOR_PAIR(pair)
+    })
+}
 
-        for (text, answer) in data {
-            if let Some(s) = text {
-                to_render.push_str(s);
-            }
+impl Curses {
+    /// Draws `line` at the start of `row`.
+    fn draw_line(&self, win: ncurses::WINDOW, row: i32, line: &[Span]) {
+        ncurses::wmove(win, row, 0);
 
-            if *answer {
-                match pos.cmp(&answers.len()) {
-                    std::cmp::Ordering::Less => to_render.push_str(&answers[pos]),
-                    std::cmp::Ordering::Equal => {
-                        to_render.push_str(current);
-                        ncurses::addstr(&to_render);
-                        to_render.clear();
-                    }
-                    std::cmp::Ordering::Greater => to_render.push_str("___"),
-                }
-                pos += 1;
-            }
+        for (paint, text) in line {
+            let attr = Paint::STYLED.iter().position(|p| p == paint).map_or(ncurses::A_NORMAL(), |idx| self.attrs[idx]);
+            ncurses::wattron(win, attr);
+            ncurses::waddstr(win, text);
+            ncurses::wattroff(win, attr);
         }
-
-        let mut x = 0;
-        let mut y = 0;
-
-        ncurses::getyx(ncurses::stdscr(), &mut y, &mut x);
-
-        ncurses::addstr(&to_render);
-
-        ncurses::mv(y, x);
-
-        to_render
     }
 }
 
-impl Render for NCurses {
-    fn ask<'a, I>(&mut self, question: I) -> Result<Vec<String>, String> where I: iter::Iterator<Item = (Option<&'a str>, bool)>{
-        let mut answers = Vec::new();
-        let mut current = String::new();
-
-        let question = question.collect::<Vec<_>>();
-
-        self.render_partially_answered(&answers, question.iter(), &current);
-
-        for (_, answer) in &question {
-            if *answer {
-                'word: loop {
-                    let ch = Self::get_char();
-
-                    match ch {
-                        '\n' => {
-                            answers.push(current);
-                            current = String::new();
-
-                            self.render_partially_answered(&answers, question.iter(), &current);
-
-                            break 'word;
-                        }
-                        _ => {
-                            if ch as u32 == 127 {
-                                // TODO(#1): Going back and editing previous answers
-                                // if let None = current.pop() {
-                                //     if let Some(ans) = answers.pop() {
-                                //         current = ans;
-                                //     }
-                                // }
-                                current.pop();
-                            } else {
-                                current.push(ch);
-                            }
-                        }
-                    }
-
-                    self.render_partially_answered(&answers, question.iter(), &current);
-                }
-            }
-        }
-
-        let s = self.render_partially_answered(&answers, question.iter(), &current);
-        self.screen.push_str(&s);
-        self.screen.push('\n');
-        Ok(answers)
-    }
-
-
-    fn init() -> Result<Self, String> {
+impl Backend for Curses {
+    fn open(theme: &Theme) -> Result<Self, String> {
+        // Without this, anything outside of ASCII comes out garbled, including the line under the history
+        ncurses::setlocale(ncurses::LcCategory::ctype, "");
         ncurses::initscr();
         ncurses::cbreak();
         ncurses::noecho();
 
+        // The windows are only made once the layout is known
         Ok(Self {
-            screen: String::new()
+            header_win: ptr::null_mut(),
+            history_win: ptr::null_mut(),
+            input_win: ptr::null_mut(),
+            attrs: attributes(theme),
         })
     }
 
-    fn finish(&mut self) -> Result<(), String> {
-        ncurses::curs_set(ncurses::CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+    fn size(&self) -> (usize, usize) {
+        let (rows, cols) = size(ncurses::stdscr());
+        (rows as usize, cols as usize)
+    }
 
-        ncurses::clear();
-        ncurses::addstr(&self.screen);
+    fn layout(&mut self, layout: &Layout) {
+        for win in [self.header_win, self.history_win, self.input_win] {
+            if !win.is_null() {
+                ncurses::delwin(win);
+            }
+        }
+        ncurses::erase();
+        ncurses::wnoutrefresh(ncurses::stdscr());
 
-        Self::print("PROGRAM FINISHED. PRESS ANY KEY TO EXIT");
+        let cols = layout.cols as i32;
+        let history = layout.history as i32;
 
-        ncurses::refresh();
+        self.header_win = ncurses::newwin(1, cols, 0, 0);
+        self.history_win = ncurses::newwin(history, cols, 1, 0);
+        self.input_win = ncurses::newwin(layout.input as i32 + 1, cols, history + 1, 0);
 
-        Self::get_char(); // Pause so that the person can see the finish of the program
+        ncurses::keypad(self.input_win, true);
+        // Wake up every second, even without a key press, to keep the clock in the status line going
+        ncurses::wtimeout(self.input_win, 1000);
+    }
 
-        ncurses::endwin();
+    fn draw_header(&mut self, line: &str) {
+        ncurses::werase(self.header_win);
 
-        Ok(())
+        if !line.is_empty() {
+            let (_, cols) = size(self.header_win);
+            ncurses::wattron(self.header_win, ncurses::A_REVERSE());
+            ncurses::mvwaddstr(self.header_win, 0, 0, &format!("{line:<width$}", width = cols as usize));
+            ncurses::wattroff(self.header_win, ncurses::A_REVERSE());
+        }
+
+        ncurses::wnoutrefresh(self.header_win);
     }
 
-    fn display_error(&mut self, err: &str) {
-        Self::print("ERROR: ");
-        Self::print(err);
-        Self::print(". Press any button to continue.\n");
-        Self::get_char();
-        ncurses::clear();
-        ncurses::addstr(&self.screen);
+    fn draw_history(&mut self, lines: &[Vec<Span>]) {
+        ncurses::werase(self.history_win);
+        for (row, line) in lines.iter().enumerate() {
+            self.draw_line(self.history_win, row as i32, line);
+        }
+        ncurses::wnoutrefresh(self.history_win);
     }
 
-    fn show_result(&mut self, correction: Option<String>) {
-        if let Some(correction) = correction {
-            Self::print("INCORRECT. The correct answer is: \"");
-            Self::print(&correction);
-            Self::print("\".\n");
+    fn draw_input(&mut self, notice: Option<&str>, lines: &[Vec<Span>], cursor: Option<(usize, usize)>) {
+        let win = self.input_win;
+        let (_, cols) = size(win);
 
-            let mut response = String::new();
+        ncurses::werase(win);
+        ncurses::mvwhline(win, 0, 0, ncurses::ACS_HLINE(), cols);
+        if let Some(notice) = notice {
+            ncurses::mvwaddstr(win, 0, 2, notice);
+        }
 
-            'response: loop {
+        for (row, line) in lines.iter().enumerate() {
+            self.draw_line(win, row as i32 + 1, line);
+        }
 
-                Self::print("Please type that out: ");
+        match cursor {
+            Some((row, col)) => {
+                ncurses::curs_set(ncurses::CURSOR_VISIBILITY::CURSOR_VISIBLE);
+                ncurses::wmove(win, row as i32 + 1, col as i32);
+            }
+            None => {
+                ncurses::curs_set(ncurses::CURSOR_VISIBILITY::CURSOR_INVISIBLE);
+            }
+        }
 
-                loop {
-                    let ch = Self::get_char();
+        ncurses::wnoutrefresh(win);
+        ncurses::doupdate();
+    }
 
-                    if ch == '\n' {
-                        if response.trim() == correction.trim().to_lowercase() {
-                            break 'response;
-                        } else {
-                            continue 'response;
-                        }
-                    } else {
-                        response.push(ch.to_lowercase().next().unwrap())
-                    }
-                }
-            }
+    fn read(&mut self) -> Input {
+        match ncurses::wget_wch(self.input_win) {
+            Some(ncurses::WchResult::Char(c)) => match char::from_u32(c) {
+                Some('\n' | '\r') => Input::Enter,
+                Some('\t') => Input::Tab,
+                // Terminals send Backspace either as a character or as a key code
+                Some('\x7f' | '\x08') => Input::Backspace,
+                Some(ch) if !ch.is_control() => Input::Char(ch),
+                _ => Input::Other,
+            },
+            Some(ncurses::WchResult::KeyCode(code)) => match code {
+                ncurses::KEY_ENTER => Input::Enter,
+                ncurses::KEY_BTAB => Input::BackTab,
+                ncurses::KEY_BACKSPACE => Input::Backspace,
+                ncurses::KEY_DC => Input::Delete,
+                ncurses::KEY_LEFT => Input::Left,
+                ncurses::KEY_RIGHT => Input::Right,
+                ncurses::KEY_UP => Input::Up,
+                ncurses::KEY_DOWN => Input::Down,
+                ncurses::KEY_HOME => Input::Home,
+                ncurses::KEY_END => Input::End,
+                ncurses::KEY_PPAGE => Input::PageUp,
+                ncurses::KEY_NPAGE => Input::PageDown,
+                ncurses::KEY_RESIZE => Input::Resize,
+                _ => Input::Other,
+            },
+            None => Input::Tick,
         }
     }
+
+    fn close(&mut self) {
+        ncurses::endwin();
+    }
 }
@@ -0,0 +1,55 @@
+use std::fmt;
+
+/// One question answered during an exam.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answered {
+    /// The question as it was asked, with `___` for each blank.
+    pub prompt: String,
+    pub given: Vec<String>,
+    /// What each blank accepts, in the same order as `given`.
+    pub expected: Vec<String>,
+    pub correct: bool,
+}
+
+/// Everything answered during an exam, shown all at once at the end instead of after each question.
+#[derive(Debug, Default)]
+pub struct ExamReport {
+    answered: Vec<Answered>,
+}
+
+impl ExamReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, answered: Answered) {
+        self.answered.push(answered);
+    }
+
+    pub fn score(&self) -> usize {
+        self.answered.iter().filter(|a| a.correct).count()
+    }
+}
+
+impl fmt::Display for ExamReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.answered.len();
+        let percent = (self.score() * 100).checked_div(total).unwrap_or(0);
+
+        writeln!(f, "Score: {}/{total} ({percent}%)", self.score())?;
+
+        let wrong = self.answered.iter().filter(|a| !a.correct).collect::<Vec<_>>();
+        if wrong.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Wrong answers:")?;
+        for (idx, answered) in wrong.iter().enumerate() {
+            writeln!(f, "{}. {}", idx + 1, answered.prompt)?;
+            writeln!(f, "   Given:    {}", answered.given.join(", "))?;
+            writeln!(f, "   Expected: {}", answered.expected.join(", "))?;
+        }
+        Ok(())
+    }
+}
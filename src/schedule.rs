@@ -0,0 +1,123 @@
+//! Spaced repetition using the SM-2 algorithm.
+//!
+//! Nothing about the schedule is stored on its own. Instead, the state of each question is worked out by replaying
+//! every attempt at it from the progress file, so the schedule can never disagree with the history.
+
+use std::fmt;
+
+use crate::progress::{Attempt, DAY};
+
+/// How easily the learner remembered an answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rating {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Rating {
+    /// The grade from 0 to 5 that SM-2 works with.
+    fn quality(self) -> f64 {
+        match self {
+            Rating::Again => 1.0,
+            Rating::Hard => 3.0,
+            Rating::Good => 4.0,
+            Rating::Easy => 5.0,
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "again" => Some(Rating::Again),
+            "hard" => Some(Rating::Hard),
+            "good" => Some(Rating::Good),
+            "easy" => Some(Rating::Easy),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Rating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rating::Again => write!(f, "again"),
+            Rating::Hard => write!(f, "hard"),
+            Rating::Good => write!(f, "good"),
+            Rating::Easy => write!(f, "easy"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    pub ease: f64,
+    pub interval_days: f64,
+    pub repetitions: u32,
+    /// Seconds since the unix epoch.
+    pub due: u64,
+}
+
+impl Default for Card {
+    fn default() -> Self {
+        Self { ease: 2.5, interval_days: 0.0, repetitions: 0, due: 0 }
+    }
+}
+
+impl Card {
+    /// Replays `attempts` to find out where a question is in its schedule, or `None` if it has never been asked.
+    ///
+    /// Retries within a session only count as practice, so they don't move the schedule.
+    pub fn from_history(attempts: &[Attempt]) -> Option<Self> {
+        let mut attempts = attempts.iter().filter(|a| a.retry == 0).peekable();
+        attempts.peek()?;
+
+        let mut card = Card::default();
+        for attempt in attempts {
+            let rating = attempt.rating.unwrap_or(if attempt.correct { Rating::Good } else { Rating::Again });
+            card.review(rating, attempt.time);
+        }
+        Some(card)
+    }
+
+    pub fn review(&mut self, rating: Rating, time: u64) {
+        let quality = rating.quality();
+
+        if quality < 3.0 {
+            self.repetitions = 0;
+            self.interval_days = 1.0;
+        } else {
+            self.repetitions += 1;
+            self.interval_days = match self.repetitions {
+                1 => 1.0,
+                2 => 6.0,
+                _ => (self.interval_days * self.ease).round(),
+            };
+        }
+
+        self.ease = (self.ease + 0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02)).max(1.3);
+        self.due = time + (self.interval_days * DAY as f64) as u64;
+    }
+
+    pub fn is_due(&self, now: u64) -> bool {
+        self.due <= now
+    }
+}
+
+/// Picks out the items that should be studied at `now`: due items first, most overdue first, then new ones in the
+/// order they were given.
+pub fn schedule<T>(items: Vec<(T, Option<Card>)>, now: u64) -> Vec<T> {
+    let mut due = Vec::new();
+    let mut new = Vec::new();
+
+    for (item, card) in items {
+        match card {
+            Some(card) if card.is_due(now) => due.push((card.due, item)),
+            Some(_) => {}
+            None => new.push(item),
+        }
+    }
+
+    due.sort_by_key(|(due, _)| *due);
+    due.into_iter().map(|(_, item)| item).chain(new).collect()
+}
@@ -0,0 +1,354 @@
+//! A study session: picking which questions to ask and in what order, grading the answers, asking missed questions
+//! again and recording how it all went. Any `Render` can show one.
+
+use std::{collections::{HashMap, VecDeque}, time};
+
+use crate::{
+    input,
+    leitner::Leitner,
+    lint::Linter,
+    progress::{self, Attempt, Progress, DAY},
+    question::{Parser, Question},
+    random::Rng,
+    render::{Render, Status},
+    report::{Answered, ExamReport},
+    schedule::{self, Card, Rating},
+    stats,
+    summary::Summary,
+};
+
+pub enum Shuffle {
+    None,
+    WithinFiles,
+    AcrossFiles,
+}
+
+/// How a session picks and asks its questions.
+pub struct Options {
+    pub lint: bool,
+    pub srs: bool,
+    pub leitner: Option<Leitner>,
+    pub shuffle: Shuffle,
+    pub shuffle_choices: bool,
+    pub seed: Option<u64>,
+    pub max_retries: u32,
+    pub retry_gap: usize,
+    pub question_limit: Option<usize>,
+    pub time_limit: Option<time::Duration>,
+    pub due_only: bool,
+    pub new_per_day: Option<usize>,
+    /// Correct answers that took longer than this on any blank are rated as hard.
+    pub slow_after: Option<time::Duration>,
+    pub exam: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            lint: false,
+            srs: false,
+            leitner: None,
+            shuffle: Shuffle::None,
+            shuffle_choices: false,
+            seed: None,
+            max_retries: 3,
+            retry_gap: 3,
+            question_limit: None,
+            time_limit: None,
+            due_only: false,
+            new_per_day: None,
+            slow_after: None,
+            exam: false,
+        }
+    }
+}
+
+pub struct Session {
+    options: Options,
+    /// The files being studied, as pairs of paths and their contents.
+    files: Vec<(String, String)>,
+    progress: Option<Progress>,
+    /// The number of the Leitner session this is, if it's studied with Leitner boxes.
+    leitner_session: Option<u64>,
+    rng: Rng,
+    /// The questions still to be asked, with the file they came from and how many times they've been retried.
+    queue: VecDeque<(String, Result<Question, String>, u32)>,
+    /// The section each question is in, by file and question.
+    sections: HashMap<(String, String), Option<String>>,
+    summary: Summary,
+    report: ExamReport,
+}
+
+impl Session {
+    /// Picks the questions to ask out of `files`, which can depend on what `progress` says about them.
+    pub fn new(files: Vec<(String, String)>, options: Options, mut progress: Option<Progress>) -> Result<Self, String> {
+        let mut questions = Vec::new();
+        for (path, src) in &files {
+            let mut parser = Parser::new(src, path);
+            while let Some(maybe_question) = parser.next() {
+                // There's nothing to ask or grade without blanks, so it's shown like any other mistake in the file
+                let maybe_question = maybe_question.and_then(|q| match q.view().blanks().next() {
+                    Some(_) => Ok(q),
+                    None => Err(format!("{path}:{} Question has no blanks to fill in!", parser.line_number())),
+                });
+                questions.push((path.clone(), maybe_question));
+            }
+        }
+
+        let mut rng = options.seed.map(Rng::new).unwrap_or_else(Rng::from_time);
+
+        match options.shuffle {
+            Shuffle::None => {}
+            Shuffle::WithinFiles => {
+                for file in questions.chunk_by_mut(|a, b| a.0 == b.0) {
+                    rng.shuffle(file);
+                }
+            }
+            Shuffle::AcrossFiles => rng.shuffle(&mut questions),
+        }
+
+        // Only the schedulers know what's due, so without one every question that's been seen would be left in
+        if options.due_only && !options.srs && options.leitner.is_none() {
+            return Err(String::from("`--due-only` needs `--srs` or `--leitner` to know which questions are due!"));
+        }
+
+        if options.srs {
+            let Some(progress) = &progress else {
+                return Err(String::from("Spaced repetition needs a progress file to know what's due!"));
+            };
+
+            let cards = questions.into_iter().map(|item| {
+                let card = match &item.1 {
+                    Ok(q) => Card::from_history(progress.attempts(&q.to_string())),
+                    Err(_) => None,
+                };
+                (item, card)
+            }).collect();
+
+            questions = schedule::schedule(cards, progress::now());
+        }
+
+        let mut leitner_session = None;
+        if let Some(leitner) = &options.leitner {
+            let Some(progress) = &mut progress else {
+                return Err(String::from("Leitner boxes need a progress file to know which box each question is in!"));
+            };
+
+            // Saved straight away, so that the session counts even if nothing in it is due
+            let session = progress.start_leitner_session()?;
+            leitner_session = Some(session);
+
+            questions.retain(|(_, maybe_question)| match maybe_question {
+                Ok(q) => leitner.is_due(leitner.box_of(progress.attempts(&q.to_string())), session),
+                Err(_) => true,
+            });
+        }
+
+        if options.due_only || options.new_per_day.is_some() {
+            let Some(progress) = &progress else {
+                return Err(String::from("Limiting new questions needs a progress file to know which ones are new!"));
+            };
+
+            let mut new_allowed = options.new_per_day
+                .map(|n| n.saturating_sub(progress.introduced_since(progress::now().saturating_sub(DAY))));
+
+            questions.retain(|(_, maybe_question)| match maybe_question {
+                Ok(q) if progress.attempts(&q.to_string()).is_empty() => match &mut new_allowed {
+                    _ if options.due_only => false,
+                    Some(0) => false,
+                    Some(n) => {
+                        *n -= 1;
+                        true
+                    }
+                    None => true,
+                },
+                _ => true,
+            });
+        }
+
+        let mut sections = HashMap::new();
+        for (path, src) in &files {
+            for (question, placement) in stats::outline(src, path) {
+                sections.entry((path.clone(), question)).or_insert(placement.section);
+            }
+        }
+
+        Ok(Self {
+            options,
+            files,
+            progress,
+            leitner_session,
+            rng,
+            queue: questions.into_iter().map(|(path, q)| (path, q, 0)).collect(),
+            sections,
+            summary: Summary::new(),
+            report: ExamReport::new(),
+        })
+    }
+
+    pub fn summary(&self) -> &Summary {
+        &self.summary
+    }
+
+    pub fn report(&self) -> &ExamReport {
+        &self.report
+    }
+
+    /// Shows anything in the files that's allowed but probably a mistake.
+    fn lint<R: Render>(&self, window: &mut R) {
+        let mut linter = Linter::new();
+        for (path, src) in &self.files {
+            let mut parser = Parser::new(src, path);
+            while let Some(maybe_question) = parser.next_located() {
+                if let Ok(q) = maybe_question {
+                    let line = parser.line_number();
+                    for warning in linter.lint(&q, path, line) {
+                        window.display_warning(&format!("{path}:{line} {warning}"));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Asks every question on `window`, until there are none left or the session runs out of time or questions, and
+    /// then shows how it went.
+    pub fn run<R: Render>(&mut self, window: &mut R) -> Result<(), String> {
+        if self.options.lint {
+            self.lint(window);
+        }
+
+        let session = progress::now();
+        let started = time::Instant::now();
+
+        let mut total = self.queue.iter().filter(|(_, q, _)| q.is_ok()).count();
+        if let Some(limit) = self.options.question_limit {
+            total = total.min(limit);
+        }
+        let mut position = 0;
+
+        while let Some((path, maybe_question, retry)) = self.queue.pop_front() {
+            match maybe_question {
+                Err(e) => window.display_error(&e),
+                Ok(q) => {
+                    if self.options.time_limit.is_some_and(|limit| started.elapsed() >= limit) {
+                        break;
+                    }
+                    // Retries don't count towards the limit, so the questions already asked still get them
+                    if retry == 0 && self.options.question_limit.is_some_and(|limit| position >= limit) {
+                        continue;
+                    }
+                    if retry == 0 {
+                        position += 1;
+                    }
+
+                    window.show_status(&Status {
+                        file: path.clone(),
+                        section: self.sections.get(&(path.clone(), q.to_string())).cloned().flatten(),
+                        position,
+                        total,
+                        score: (!self.options.exam).then(|| (self.summary.correct(), self.summary.answers())),
+                        retries: self.queue.iter().filter(|(_, _, retry)| *retry > 0).count(),
+                        started,
+                        time_limit: self.options.time_limit,
+                    });
+
+                    let Some(correct) = self.ask(window, &path, &q, retry, session)? else {
+                        break;
+                    };
+                    if !correct && !self.options.exam && retry < self.options.max_retries {
+                        // The first miss comes back a few questions later, and any after that at the end
+                        let position = match retry {
+                            0 => self.options.retry_gap.min(self.queue.len()),
+                            _ => self.queue.len(),
+                        };
+                        self.queue.insert(position, (path, Ok(q), retry + 1));
+                    }
+                }
+            }
+        }
+
+        if self.options.exam {
+            window.show_report(&self.report.to_string());
+        }
+
+        self.summary.set_elapsed(started.elapsed());
+        window.show_summary(&self.summary);
+
+        Ok(())
+    }
+
+    /// Asks `q` once, grades it and records how it went, giving back whether it was answered correctly, or `None` if
+    /// the learner stopped the session.
+    fn ask<R: Render>(&mut self, window: &mut R, path: &str, q: &Question, retry: u32, session: u64) -> Result<Option<bool>, String> {
+        let exam = self.options.exam;
+
+        // The progress file knows questions by how they were written, not how they were shuffled
+        let mut shown = q.clone();
+        if self.options.shuffle_choices {
+            shown.shuffle_choices(&mut self.rng);
+        }
+
+        let Some(response) = input::ask(window, &shown.view())? else {
+            return Ok(None);
+        };
+        // Stopping after the answers were given still records them
+        let mut stopped = false;
+        let answers = response.answers.clone();
+        let slow = self.options.slow_after.is_some_and(|limit| response.slowest_blank() > limit);
+
+        let grades = shown.grade(&answers);
+        let correction = shown.check_answers(answers.clone());
+        let correct = correction.is_none();
+
+        self.summary.record(&q.to_string(), &shown.prompt(), correct, response.total);
+        self.summary.record_first_keys(response.timings.iter().map(|t| t.first_key));
+
+        if exam {
+            // Nothing about how it went is shown until the end
+            self.report.push(Answered {
+                prompt: shown.prompt(),
+                given: answers.clone(),
+                expected: shown.expected(),
+                correct,
+            });
+        } else {
+            window.show_result(&grades, correction.as_deref());
+            if let Some(correction) = &correction {
+                stopped = !input::type_out(window, correction)?;
+            }
+        }
+
+        let rating = match (retry == 0 && !exam, correct) {
+            (false, _) => None,
+            (true, false) => self.options.srs.then_some(Rating::Again),
+            // Getting there only after a long think is hard recall, whatever the learner would say
+            (true, true) if slow => Some(Rating::Hard),
+            (true, true) if self.options.srs => {
+                let rating = input::rate_recall(window)?;
+                stopped = rating.is_none();
+                rating
+            }
+            (true, true) => None,
+        };
+
+        if let Some(progress) = &mut self.progress {
+            let attempt = Attempt {
+                question: q.to_string(),
+                file: path.to_string(),
+                session,
+                time: progress::now(),
+                correct,
+                score: grades.iter().filter(|g| **g).count() as f64 / grades.len().max(1) as f64,
+                answers,
+                rating,
+                retry,
+                leitner: self.leitner_session,
+            };
+            if let Err(e) = progress.record(attempt) {
+                window.display_error(&e);
+            }
+        }
+
+        Ok((!stopped).then_some(correct))
+    }
+}
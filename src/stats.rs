@@ -0,0 +1,340 @@
+//! Long-term statistics from the progress file, for `quickstudy stats`.
+//!
+//! Sections and tags come from comments in the study files: a comment starting with `##` starts a new section, and a
+//! `# tags: a, b` comment tags every question after it, until the next `# tags:` comment or section.
+
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, fs, path::PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::{progress::{self, Attempt, Progress, DAY}, question::{Line, Parser}, schedule::Card};
+
+/// How many days ahead reviews are forecast.
+const FORECAST_DAYS: u64 = 7;
+
+/// Where a question sits in its file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Placement {
+    pub section: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// Finds the section and tags of every question in `src`, keyed by the question's canonical form.
+pub fn outline(src: &str, path: &str) -> Vec<(String, Placement)> {
+    let mut parser = Parser::new(src, path);
+    let mut current = Placement::default();
+    let mut questions = Vec::new();
+
+    while let Some(line) = parser.next_line() {
+        match line {
+            Line::Comment(comment) => {
+                let comment = comment.trim();
+                if let Some(section) = comment.strip_prefix("##") {
+                    current = Placement { section: Some(section.trim().to_string()), tags: Vec::new() };
+                } else if let Some(tags) = comment.trim_start_matches('#').trim().strip_prefix("tags:") {
+                    current.tags = tags.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+                }
+            }
+            Line::Question(Ok(syntax)) => questions.push((syntax.to_question().to_string(), current.clone())),
+            Line::Question(Err(_)) | Line::Blank => {}
+        }
+    }
+
+    questions
+}
+
+/// Accuracy and retention for a group of questions, like a file or a tag.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Group {
+    pub attempts: usize,
+    pub correct: usize,
+    /// Attempts and correct attempts in the last 7 days.
+    pub week: (usize, usize),
+    /// Attempts and correct attempts in the last 30 days.
+    pub month: (usize, usize),
+    /// Reviews of questions that had been seen before, and how many of them were remembered.
+    pub reviews: (usize, usize),
+}
+
+fn ratio((total, correct): (usize, usize)) -> Option<f64> {
+    (total > 0).then(|| correct as f64 / total as f64)
+}
+
+impl Group {
+    fn add(&mut self, attempt: &Attempt, first: bool, now: u64) {
+        let correct = attempt.correct as usize;
+        let age = now.saturating_sub(attempt.time);
+
+        self.attempts += 1;
+        self.correct += correct;
+        if age < 7 * DAY {
+            self.week.0 += 1;
+            self.week.1 += correct;
+        }
+        if age < 30 * DAY {
+            self.month.0 += 1;
+            self.month.1 += correct;
+        }
+        if !first {
+            self.reviews.0 += 1;
+            self.reviews.1 += correct;
+        }
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        ratio((self.attempts, self.correct))
+    }
+
+    pub fn retention(&self) -> Option<f64> {
+        ratio(self.reviews)
+    }
+
+    fn to_json(&self, name: &str) -> Value {
+        json!({
+            "name": name,
+            "attempts": self.attempts,
+            "accuracy": self.accuracy(),
+            "accuracy_7d": ratio(self.week),
+            "accuracy_30d": ratio(self.month),
+            "retention": self.retention(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    pub overall: Group,
+    pub files: BTreeMap<String, Group>,
+    pub sections: BTreeMap<String, Group>,
+    pub tags: BTreeMap<String, Group>,
+    /// How many questions are due on each of the next days, starting with today. Overdue questions count as today.
+    pub forecast: Vec<usize>,
+    pub current_streak: u64,
+    pub longest_streak: u64,
+}
+
+impl Stats {
+    /// Works out the statistics for everything in `history`, using `placements` to find sections and tags.
+    ///
+    /// Only first tries count, since retries within a session are just practice.
+    pub fn new<'a>(
+        history: impl Iterator<Item = (&'a str, &'a [Attempt])>,
+        placements: &HashMap<String, Placement>,
+        now: u64,
+    ) -> Self {
+        let mut stats = Stats { forecast: vec![0; FORECAST_DAYS as usize], ..Stats::default() };
+        let mut days = BTreeSet::new();
+        let today = now / DAY;
+
+        for (question, attempts) in history {
+            let placement = placements.get(question);
+
+            for (idx, attempt) in attempts.iter().filter(|a| a.retry == 0).enumerate() {
+                let first = idx == 0;
+                days.insert(attempt.time / DAY);
+
+                stats.overall.add(attempt, first, now);
+                stats.files.entry(attempt.file.clone()).or_default().add(attempt, first, now);
+
+                if let Some(placement) = placement {
+                    if let Some(section) = &placement.section {
+                        stats.sections.entry(section.clone()).or_default().add(attempt, first, now);
+                    }
+                    for tag in &placement.tags {
+                        stats.tags.entry(tag.clone()).or_default().add(attempt, first, now);
+                    }
+                }
+            }
+
+            if let Some(card) = Card::from_history(attempts) {
+                let day = (card.due / DAY).saturating_sub(today);
+                if let Some(count) = stats.forecast.get_mut(day as usize) {
+                    *count += 1;
+                }
+            }
+        }
+
+        // A streak is still going if nothing has been studied yet today.
+        let mut day = if days.contains(&today) { today } else { today.saturating_sub(1) };
+        while days.contains(&day) {
+            stats.current_streak += 1;
+            day = match day.checked_sub(1) {
+                Some(day) => day,
+                None => break,
+            };
+        }
+
+        let mut run = 0;
+        let mut last = None;
+        for day in days {
+            run = if day.checked_sub(1).is_some_and(|before| last == Some(before)) { run + 1 } else { 1 };
+            stats.longest_streak = stats.longest_streak.max(run);
+            last = Some(day);
+        }
+
+        stats
+    }
+
+    fn groups(&self) -> [(&'static str, &BTreeMap<String, Group>); 3] {
+        [("file", &self.files), ("section", &self.sections), ("tag", &self.tags)]
+    }
+
+    pub fn to_json(&self) -> Value {
+        let groups = |groups: &BTreeMap<String, Group>| {
+            groups.iter().map(|(name, group)| group.to_json(name)).collect::<Vec<_>>()
+        };
+
+        json!({
+            "overall": self.overall.to_json("overall"),
+            "files": groups(&self.files),
+            "sections": groups(&self.sections),
+            "tags": groups(&self.tags),
+            "forecast": self.forecast.iter().enumerate().map(|(day, due)| json!({ "day": day, "due": due })).collect::<Vec<_>>(),
+            "streak": { "current": self.current_streak, "longest": self.longest_streak },
+        })
+    }
+
+    pub fn to_csv(&self) -> String {
+        let percent = |ratio: Option<f64>| ratio.map(|r| format!("{r:.3}")).unwrap_or_default();
+        let escape = |s: &str| {
+            if s.contains([',', '"', '\n']) {
+                format!("\"{}\"", s.replace('"', "\"\""))
+            } else {
+                s.to_string()
+            }
+        };
+
+        let mut csv = String::from("kind,name,attempts,accuracy,accuracy_7d,accuracy_30d,retention\n");
+
+        let mut rows = vec![("overall", "overall", &self.overall)];
+        for (kind, groups) in self.groups() {
+            for (name, group) in groups {
+                rows.push((kind, name, group));
+            }
+        }
+
+        for (kind, name, group) in rows {
+            csv.push_str(&format!(
+                "{kind},{},{},{},{},{},{}\n",
+                escape(name),
+                group.attempts,
+                percent(group.accuracy()),
+                percent(ratio(group.week)),
+                percent(ratio(group.month)),
+                percent(group.retention()),
+            ));
+        }
+
+        for (day, due) in self.forecast.iter().enumerate() {
+            csv.push_str(&format!("forecast,+{day},{due},,,,\n"));
+        }
+        csv.push_str(&format!("streak,current,{},,,,\n", self.current_streak));
+        csv.push_str(&format!("streak,longest,{},,,,\n", self.longest_streak));
+
+        csv
+    }
+
+    pub fn to_table(&self) -> String {
+        let percent = |ratio: Option<f64>| ratio.map(|r| format!("{:.0}%", r * 100.0)).unwrap_or_else(|| "-".to_string());
+        let row = |name: &str, group: &Group| {
+            format!(
+                "{name:<30} {:>8} {:>9} {:>12} {:>13} {:>10}\n",
+                group.attempts,
+                percent(group.accuracy()),
+                percent(ratio(group.week)),
+                percent(ratio(group.month)),
+                percent(group.retention()),
+            )
+        };
+
+        let mut table = String::new();
+
+        for (kind, groups) in self.groups() {
+            if groups.is_empty() {
+                continue;
+            }
+            table.push_str(&format!(
+                "{:<30} {:>8} {:>9} {:>12} {:>13} {:>10}\n",
+                kind.to_uppercase(), "ATTEMPTS", "ACCURACY", "LAST 7 DAYS", "LAST 30 DAYS", "RETENTION"
+            ));
+            for (name, group) in groups {
+                table.push_str(&row(name, group));
+            }
+            table.push('\n');
+        }
+        table.push_str(&row("Overall", &self.overall));
+
+        table.push_str("\nDue in the next week:\n");
+        for (day, due) in self.forecast.iter().enumerate() {
+            let when = match day {
+                0 => "Today".to_string(),
+                1 => "Tomorrow".to_string(),
+                _ => format!("In {day} days"),
+            };
+            table.push_str(&format!("  {when:<12} {due}\n"));
+        }
+
+        table.push_str(&format!(
+            "\nStreak: {} day(s), longest {} day(s)\n",
+            self.current_streak, self.longest_streak
+        ));
+
+        table
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+/// Entry point for `quickstudy stats [--progress <file>] [--format table|csv|json] [files]`.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut progress_path = Progress::default_path();
+    let mut format = Format::Table;
+    let mut paths = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--progress" => match args.next() {
+                Some(path) => progress_path = Some(PathBuf::from(path)),
+                None => return Err(String::from("Expected a file after `--progress`!")),
+            },
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("table") => Format::Table,
+                    Some("csv") => Format::Csv,
+                    Some("json") => Format::Json,
+                    Some(other) => return Err(format!("Unknown output format `{other}`!")),
+                    None => return Err(String::from("Expected an output format after `--format`!")),
+                }
+            }
+            _ => paths.push(arg),
+        }
+    }
+
+    let progress_path = progress_path.ok_or_else(|| String::from("Could not find the progress file!"))?;
+    let progress = Progress::read(&progress_path)?;
+
+    // The study files are only needed to know the sections and tags.
+    let mut placements = HashMap::new();
+    for path in paths {
+        let src = fs::read_to_string(path).map_err(|e| format!("Could not read file `{path}`: {e}!"))?;
+        for (question, placement) in outline(&src, path) {
+            placements.entry(question).or_insert(placement);
+        }
+    }
+
+    let stats = Stats::new(progress.all(), &placements, progress::now());
+
+    match format {
+        Format::Table => print!("{}", stats.to_table()),
+        Format::Csv => print!("{}", stats.to_csv()),
+        Format::Json => println!("{}", stats.to_json()),
+    }
+
+    Ok(())
+}
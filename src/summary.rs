@@ -0,0 +1,135 @@
+use std::{fmt, time::Duration};
+
+use serde_json::{json, Value};
+
+/// How a single question went over a session, including its retries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Tally {
+    key: String,
+    prompt: String,
+    asked: usize,
+    missed: usize,
+}
+
+/// Statistics about the session that just ended.
+#[derive(Debug, Default)]
+pub struct Summary {
+    tallies: Vec<Tally>,
+    response_times: Vec<Duration>,
+    /// How long it took to start typing in each blank, for the blanks that were timed on their own.
+    first_keys: Vec<Duration>,
+    elapsed: Duration,
+}
+
+/// How many of the most missed questions get listed.
+const HARDEST: usize = 5;
+
+impl Summary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one answer. `key` identifies the question, and `prompt` is how it's shown.
+    pub fn record(&mut self, key: &str, prompt: &str, correct: bool, took: Duration) {
+        let idx = match self.tallies.iter().position(|t| t.key == key) {
+            Some(idx) => idx,
+            None => {
+                self.tallies.push(Tally { key: key.to_string(), prompt: prompt.to_string(), asked: 0, missed: 0 });
+                self.tallies.len() - 1
+            }
+        };
+
+        let tally = &mut self.tallies[idx];
+        tally.asked += 1;
+        if !correct {
+            tally.missed += 1;
+        }
+        self.response_times.push(took);
+    }
+
+    /// Records how long it took to start typing in each of an answer's blanks.
+    pub fn record_first_keys(&mut self, first_keys: impl IntoIterator<Item = Duration>) {
+        self.first_keys.extend(first_keys);
+    }
+
+    pub fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+
+    pub fn answers(&self) -> usize {
+        self.response_times.len()
+    }
+
+    pub fn correct(&self) -> usize {
+        self.tallies.iter().map(|t| t.asked - t.missed).sum()
+    }
+
+    pub fn retries(&self) -> usize {
+        self.tallies.iter().map(|t| t.asked - 1).sum()
+    }
+
+    pub fn average_response_time(&self) -> Duration {
+        self.response_times
+            .iter()
+            .sum::<Duration>()
+            .checked_div(self.response_times.len() as u32)
+            .unwrap_or_default()
+    }
+
+    /// How long it took to start typing in a blank on average, if any blanks were timed.
+    pub fn average_first_key(&self) -> Option<Duration> {
+        self.first_keys.iter().sum::<Duration>().checked_div(self.first_keys.len() as u32)
+    }
+
+    fn retried(&self) -> impl Iterator<Item = &Tally> {
+        self.tallies.iter().filter(|t| t.asked > 1)
+    }
+
+    /// The questions missed the most, most missed first.
+    fn hardest(&self) -> Vec<&Tally> {
+        let mut missed = self.tallies.iter().filter(|t| t.missed > 0).collect::<Vec<_>>();
+        missed.sort_by_key(|t| std::cmp::Reverse(t.missed));
+        missed.truncate(HARDEST);
+        missed
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "questions": self.tallies.len(),
+            "answers": self.answers(),
+            "correct": self.correct(),
+            "accuracy": self.correct() as f64 / self.answers().max(1) as f64,
+            "retries": self.retries(),
+            "average_response_seconds": self.average_response_time().as_secs_f64(),
+            "average_first_key_seconds": self.average_first_key().map(|d| d.as_secs_f64()),
+            "elapsed_seconds": self.elapsed.as_secs_f64(),
+            "retried": self.retried().map(|t| t.key.clone()).collect::<Vec<_>>(),
+            "hardest": self
+                .hardest()
+                .iter()
+                .map(|t| json!({ "question": t.key, "missed": t.missed }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = (self.correct() * 100).checked_div(self.answers()).unwrap_or(0);
+
+        writeln!(f, "Questions asked:       {} ({} retries)", self.tallies.len(), self.retries())?;
+        writeln!(f, "Accuracy:              {percent}% ({}/{})", self.correct(), self.answers())?;
+        writeln!(f, "Average response time: {:.1}s", self.average_response_time().as_secs_f64())?;
+        writeln!(f, "Time spent:            {}m {}s", self.elapsed.as_secs() / 60, self.elapsed.as_secs() % 60)?;
+        writeln!(f, "Retried questions:     {}", self.retried().count())?;
+
+        let hardest = self.hardest();
+        if !hardest.is_empty() {
+            writeln!(f, "Hardest questions:")?;
+            for tally in hardest {
+                writeln!(f, "  - {} (missed {} time(s))", tally.prompt, tally.missed)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+//! The concrete syntax tree of a question, which remembers where everything came from.
+//!
+//! All spans are byte offsets into the whole source that was given to the `Parser`, so anything that goes wrong
+//! with a `Question` can be traced back to the exact text that produced it. Nothing is thrown away: the whitespace
+//! and punctuation between nodes is just whatever lies between their spans.
+
+use std::ops;
+
+use crate::question::{Answer, Question};
+
+pub type Span = ops::Range<usize>;
+
+/// A run of text between blanks, kept exactly as it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Text {
+    pub span: Span,
+    pub text: String,
+}
+
+/// An answer, a single alternative of an answer, or an item in a pool. `span` covers the trimmed text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub span: Span,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Blank {
+    /// `[a]` or `[a | b]`. `span` includes the brackets.
+    Bracketed { span: Span, alternatives: Vec<Item> },
+    /// `{n}`. `span` includes the braces, and `number_span` covers only the number.
+    Pool { span: Span, number: usize, number_span: Span },
+}
+
+impl Blank {
+    pub fn span(&self) -> &Span {
+        match self {
+            Blank::Bracketed { span, .. } | Blank::Pool { span, .. } => span,
+        }
+    }
+
+    pub fn to_answer(&self) -> Answer {
+        match self {
+            Blank::Bracketed { alternatives, .. } if alternatives.len() == 1 => Answer::Raw(alternatives[0].text.clone()),
+            Blank::Bracketed { alternatives, .. } => Answer::OneOf(alternatives.iter().map(|a| a.text.clone()).collect()),
+            Blank::Pool { number, .. } => Answer::SharedPool(number - 1),
+        }
+    }
+}
+
+/// One of the `;`-separated lists at the end of a question. `span` covers the trimmed list, without the `;`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pool {
+    pub span: Span,
+    pub items: Vec<Item>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionSyntax {
+    /// The whole line.
+    pub span: Span,
+    pub parts: Vec<(Option<Text>, Option<Blank>)>,
+    pub pools: Vec<Pool>,
+}
+
+impl QuestionSyntax {
+    pub fn to_question(&self) -> Question {
+        Question {
+            dat: self
+                .parts
+                .iter()
+                .map(|(text, blank)| (text.as_ref().map(|t| t.text.clone()), blank.as_ref().map(Blank::to_answer)))
+                .collect(),
+            pools: self
+                .pools
+                .iter()
+                .map(|pool| pool.items.iter().map(|item| item.text.clone()).collect())
+                .collect(),
+        }
+    }
+
+    /// The blanks in the order they're asked, which is also the order of the results from `Question::grade`.
+    pub fn blanks(&self) -> impl Iterator<Item = &Blank> {
+        self.parts.iter().filter_map(|(_, blank)| blank.as_ref())
+    }
+
+    /// Finds the blank whose span contains `offset`.
+    pub fn blank_at(&self, offset: usize) -> Option<&Blank> {
+        self.blanks().find(|blank| blank.span().contains(&offset))
+    }
+}
@@ -1,6 +1,12 @@
 #![cfg(test)]
+// The answers in the oldest tests are built with `vec!`, and are kept that way
+#![allow(clippy::useless_vec)]
 
+use crate::check::*;
+use crate::format::*;
+use crate::lint::*;
 use crate::question::*;
+use crate::syntax::*;
 
 #[test]
 fn parse_answer_only() {
@@ -257,3 +263,903 @@ fn shows_incorrect_answer() {
         )
     );
 }
+
+#[test]
+fn check_reports_every_error() {
+    let src = "[a] ok\n[[b]\n# comment\n\nfine [c]\nd]";
+
+    let diagnostics = check_source(src, "<test>", &mut Linter::new());
+
+    assert_eq!(
+        diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec![
+            "<test>:2:2 error: Unexpected `[`!".to_string(),
+            "<test>:6:2 error: Unexpected `]`!".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn check_json_has_positions() {
+    let diagnostics = check_source("{1} and {2}; a", "<test>", &mut Linter::new());
+
+    assert_eq!(
+        diagnostics.iter().map(Diagnostic::to_json).collect::<Vec<_>>(),
+        vec![serde_json::json!({
+            "path": "<test>",
+            "line": 1,
+            "column": 12,
+            "severity": "error",
+            "message": "Expected 2 pools, but found 1!",
+        })]
+    );
+
+    // A set that isn't there would break the session, so it's an error and not just a warning
+    let diagnostics = check_source("Capital of France is {2}; Paris, Lyon", "<test>", &mut Linter::new());
+    assert_eq!(
+        diagnostics.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        vec!["<test>:1:22 error: `{2}` refers to set #2, but there are only 1 sets!".to_string()]
+    );
+}
+
+#[test]
+fn lints_suspicious_questions() {
+    let question = Parser::new("no blanks here", "<test>").next().unwrap().unwrap();
+    assert_eq!(lint_question(&question), vec!["Question has no blanks to fill in!".to_string()]);
+
+    let question = Parser::new("[a | A | ], {1} {1} {2}; x; y, y", "<test>").next().unwrap().unwrap();
+    assert_eq!(
+        lint_question(&question),
+        vec![
+            "Alternative `A` is listed more than once!".to_string(),
+            "Empty alternative in `[a | A | ]`!".to_string(),
+            "Set #1 has 1 items, but is used by 2 blanks!".to_string(),
+            "Item `y` is listed more than once in set #2!".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn lints_duplicates_across_files() {
+    let mut linter = Linter::new();
+    let first = Parser::new("The sky is [blue]", "a").next().unwrap().unwrap();
+    let second = Parser::new("the sky is [Blue] ", "b").next().unwrap().unwrap();
+
+    assert!(linter.lint(&first, "a", 3).is_empty());
+    assert_eq!(linter.lint(&second, "b", 1), vec!["Duplicate of the question at a:3!".to_string()]);
+}
+
+#[test]
+fn display_round_trips() {
+    let src = "The seasons are {1}, {1} and [the best | a good] one is [ summer ]; spring ,summer;";
+
+    let question = Parser::new(src, "<test>").next().unwrap().unwrap();
+    let printed = question.to_string();
+
+    assert_eq!(printed, "The seasons are {1}, {1} and [the best | a good] one is [summer]; spring, summer");
+    assert_eq!(Parser::new(&printed, "<test>").next(), Some(Ok(question)));
+}
+
+#[test]
+fn formats_and_keeps_comments() {
+    let src = "  # A comment   \n\n\nq [a|b] ; x,y\n  [c]  \n\n";
+
+    assert_eq!(
+        format_source(src, "<test>"),
+        Ok("# A comment\n\n\nq [a | b]; x, y\n[c]\n".to_string())
+    );
+
+    let src = "# Windows\r\n\r\nq [a|b]\r\n[c]\r\n";
+    assert_eq!(format_source(src, "<test>"), Ok("# Windows\r\n\r\nq [a | b]\r\n[c]\r\n".to_string()));
+}
+
+#[test]
+fn does_not_format_broken_files() {
+    let res = format_source("# fine\n[[a]\n", "<test>");
+
+    assert_eq!(
+        res,
+        Err(vec![ParseError { line: 2, column: 2, message: "Unexpected `[`!".to_string() }])
+    );
+}
+
+fn lsp_session(messages: &[serde_json::Value]) -> Vec<serde_json::Value> {
+    let mut input = Vec::new();
+    for message in messages {
+        crate::lsp::write_message(&mut input, message).unwrap();
+    }
+
+    let mut output = Vec::new();
+    assert!(crate::lsp::Server::new().serve(&input[..], &mut output).unwrap());
+
+    let mut responses = Vec::new();
+    let mut reader = &output[..];
+    while let Some(response) = crate::lsp::read_message(&mut reader).unwrap() {
+        responses.push(response);
+    }
+    responses
+}
+
+#[test]
+fn lsp_scripted_session() {
+    use serde_json::json;
+
+    let text = "# Seasons\nThe seasons are {1} and {1}; spring, summer\n[[broken]\n";
+    let uri = "file:///deck.txt";
+
+    let responses = lsp_session(&[
+        json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+        json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+            "textDocument": { "uri": uri, "languageId": "quickstudy", "version": 1, "text": text }
+        }}),
+        json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {
+            "textDocument": { "uri": uri }, "position": { "line": 1, "character": 3 }
+        }}),
+        json!({ "jsonrpc": "2.0", "id": 3, "method": "textDocument/definition", "params": {
+            "textDocument": { "uri": uri }, "position": { "line": 1, "character": 25 }
+        }}),
+        json!({ "jsonrpc": "2.0", "id": 4, "method": "textDocument/completion", "params": {
+            "textDocument": { "uri": uri }, "position": { "line": 1, "character": 17 }
+        }}),
+        json!({ "jsonrpc": "2.0", "id": 5, "method": "shutdown" }),
+        json!({ "jsonrpc": "2.0", "method": "exit" }),
+    ]);
+
+    assert_eq!(responses.len(), 6);
+    assert_eq!(responses[0]["result"]["capabilities"]["definitionProvider"], json!(true));
+
+    assert_eq!(
+        responses[1]["params"]["diagnostics"],
+        json!([{
+            "range": { "start": { "line": 2, "character": 1 }, "end": { "line": 2, "character": 9 } },
+            "severity": 1,
+            "source": "quickstudy",
+            "message": "Unexpected `[`!",
+        }])
+    );
+
+    assert_eq!(
+        responses[2]["result"]["contents"]["value"],
+        json!("**Asked as:** The seasons are ___ and ___\n\n**Accepted answers:**\n\n1. any unused item of set #1: `spring`, `summer`\n2. any unused item of set #1: `spring`, `summer`\n")
+    );
+
+    assert_eq!(
+        responses[3]["result"]["range"],
+        json!({ "start": { "line": 1, "character": 29 }, "end": { "line": 1, "character": 43 } })
+    );
+
+    assert_eq!(
+        responses[4]["result"],
+        json!([{ "label": "{1}", "detail": "spring, summer", "insertText": "1}" }])
+    );
+
+    assert_eq!(responses[5], json!({ "jsonrpc": "2.0", "id": 5, "result": null }));
+}
+
+#[test]
+fn syntax_tree_has_spans() {
+    let src = "# comment\nA [ b | c ] {1}; x , y\n";
+    let syntax = Parser::new(src, "<test>").next_syntax().unwrap().unwrap();
+
+    assert_eq!(&src[syntax.span.clone()], "A [ b | c ] {1}; x , y");
+
+    let (text, blank) = &syntax.parts[0];
+    assert_eq!(&src[text.as_ref().unwrap().span.clone()], "A ");
+    match blank {
+        Some(Blank::Bracketed { span, alternatives }) => {
+            assert_eq!(&src[span.clone()], "[ b | c ]");
+            assert_eq!(alternatives.iter().map(|a| &src[a.span.clone()]).collect::<Vec<_>>(), vec!["b", "c"]);
+        }
+        other => panic!("Expected a bracketed blank, found {other:?}"),
+    }
+
+    match &syntax.parts[1].1 {
+        Some(Blank::Pool { span, number, number_span }) => {
+            assert_eq!(&src[span.clone()], "{1}");
+            assert_eq!(&src[number_span.clone()], "1");
+            assert_eq!(*number, 1);
+        }
+        other => panic!("Expected a pool blank, found {other:?}"),
+    }
+
+    assert_eq!(&src[syntax.pools[0].span.clone()], "x , y");
+    assert_eq!(syntax.pools[0].items.iter().map(|i| &src[i.span.clone()]).collect::<Vec<_>>(), vec!["x", "y"]);
+
+    assert_eq!(syntax.to_question(), Parser::new(src, "<test>").next().unwrap().unwrap());
+}
+
+#[test]
+fn grades_map_back_to_source() {
+    let src = "[a], [b | c], {1}; e, f";
+    let syntax = Parser::new(src, "<test>").next_syntax().unwrap().unwrap();
+
+    let answers = ["a", "d", "f"].iter().map(ToString::to_string).collect::<Vec<_>>();
+    let wrong = syntax
+        .blanks()
+        .zip(syntax.to_question().grade(&answers))
+        .filter(|(_, correct)| !correct)
+        .map(|(blank, _)| &src[blank.span().clone()])
+        .collect::<Vec<_>>();
+
+    assert_eq!(wrong, vec!["[b | c]"]);
+}
+
+#[test]
+fn parse_pool_zero() {
+    assert_eq!(
+        Parser::new("{0}; a", "<test>").next(),
+        Some(Err(String::from("<test>:1:1 Sets are numbered starting from 1!")))
+    );
+}
+
+#[test]
+fn progress_survives_a_torn_write() {
+    use crate::progress::*;
+
+    let path = std::env::temp_dir().join(format!("quickstudy-progress-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let attempt = Attempt {
+        question: "The sky is [blue]".to_string(),
+        file: "sky.txt".to_string(),
+        session: 1_700_000_000,
+        time: 1_700_000_000,
+        correct: false,
+        score: 0.0,
+        answers: vec!["green, \"ish\"".to_string()],
+        rating: None,
+        retry: 0,
+        leitner: None,
+    };
+
+    Progress::open(&path).unwrap().record(attempt.clone()).unwrap();
+
+    // Pretend that we crashed halfway through writing the next attempt
+    let mut contents = std::fs::read_to_string(&path).unwrap();
+    contents.push_str("{\"question\": \"The sky");
+    std::fs::write(&path, &contents).unwrap();
+
+    Progress::open(&path).unwrap().record(Attempt { correct: true, score: 1.0, ..attempt.clone() }).unwrap();
+
+    let saved = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .filter_map(|value| Attempt::from_json(&value))
+        .collect::<Vec<_>>();
+
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(saved, vec![attempt.clone(), Attempt { correct: true, score: 1.0, ..attempt }]);
+}
+
+#[test]
+fn sm2_intervals_grow_and_reset() {
+    use crate::schedule::*;
+
+    let day = 60 * 60 * 24;
+    let mut card = Card::default();
+
+    card.review(Rating::Good, 0);
+    assert_eq!((card.repetitions, card.interval_days, card.due), (1, 1.0, day));
+
+    card.review(Rating::Good, day);
+    assert_eq!((card.repetitions, card.interval_days, card.due), (2, 6.0, 7 * day));
+
+    card.review(Rating::Easy, 7 * day);
+    assert_eq!((card.repetitions, card.interval_days), (3, 15.0));
+    assert!((card.ease - 2.6).abs() < 1e-9);
+
+    card.review(Rating::Again, 22 * day);
+    assert_eq!((card.repetitions, card.interval_days, card.due), (0, 1.0, 23 * day));
+    assert!(card.ease < 2.6);
+}
+
+#[test]
+fn schedules_due_before_new() {
+    use crate::schedule::*;
+
+    let card = |due| Some(Card { due, ..Card::default() });
+
+    let items = vec![("new", None), ("later", card(500)), ("due", card(50)), ("overdue", card(10)), ("also new", None)];
+
+    assert_eq!(schedule(items, 100), vec!["overdue", "due", "new", "also new"]);
+}
+
+#[test]
+fn leitner_boxes_follow_answers() {
+    use crate::{leitner::*, progress::*};
+
+    let attempt = |correct| Attempt {
+        question: "q".to_string(),
+        file: "f".to_string(),
+        session: 0,
+        time: 0,
+        correct,
+        score: if correct { 1.0 } else { 0.0 },
+        answers: Vec::new(),
+        rating: None,
+        retry: 0,
+        leitner: Some(1),
+    };
+
+    let leitner = Leitner::parse("1, 2, 4").unwrap();
+
+    assert_eq!(leitner.box_of(&[]), 1);
+    assert_eq!(leitner.box_of(&[attempt(true)]), 2);
+    // Answers from sessions without Leitner boxes don't move questions between them
+    assert_eq!(leitner.box_of(&[attempt(true), Attempt { leitner: None, ..attempt(true) }]), 2);
+    assert_eq!(leitner.box_of(&[attempt(true), attempt(true), attempt(true)]), 3);
+    assert_eq!(leitner.box_of(&[attempt(true), attempt(true), attempt(false)]), 1);
+
+    assert!((1..=4).all(|session| leitner.is_due(1, session)));
+    assert_eq!((1..=4).filter(|session| leitner.is_due(2, *session)).collect::<Vec<_>>(), vec![2, 4]);
+    assert_eq!((1..=4).filter(|session| leitner.is_due(3, *session)).collect::<Vec<_>>(), vec![4]);
+
+    assert!(Leitner::parse("1, 0").is_err());
+}
+
+#[test]
+fn leitner_sessions_count_when_nothing_is_due() {
+    use crate::{leitner::Leitner, progress::Progress, render::script::Script, session::{Options, Session}};
+
+    let path = std::env::temp_dir().join(format!("quickstudy-leitner-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    // `boxes` only reads the progress file, so it doesn't make one that isn't there
+    assert!(Progress::read(&path).is_err());
+    assert!(!path.exists());
+
+    let files = vec![("f.txt".to_string(), "One is [1].\nTwo is [2].\n".to_string())];
+    let study = |answers: &[&str], leitner: bool| {
+        let options = Options { leitner: leitner.then(Leitner::default), seed: Some(1), ..Options::default() };
+        let mut session = Session::new(files.clone(), options, Some(Progress::open(&path).unwrap())).unwrap();
+        let mut script = Script::lines(answers);
+        session.run(&mut script).unwrap();
+        assert!(script.finished());
+        session.summary().answers()
+    };
+
+    // Both questions move up to box 2, then box 3, which is only due every 4 sessions
+    assert_eq!(study(&["1", "2"], true), 2);
+    assert_eq!(study(&["1", "2"], true), 2);
+    // Sessions without Leitner boxes don't count
+    assert_eq!(study(&["1", "2"], false), 2);
+    assert_eq!(study(&[], true), 0);
+    assert_eq!(Progress::read(&path).unwrap().leitner_sessions(), 3);
+    assert_eq!(study(&["1", "2"], true), 2);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn session_limits_pick_questions() {
+    use crate::{progress::Progress, render::script::Script, session::{Options, Session}};
+
+    let files = vec![("f.txt".to_string(), "1 is [1].\n2 is [2].\n3 is [3].\n4 is [4].\n".to_string())];
+    let asked = |script: &Script| {
+        script.shown.iter().filter_map(|shown| shown.strip_prefix("Answered: ")).map(|q| q[..1].to_string()).collect::<Vec<_>>()
+    };
+
+    // The retry of a missed question doesn't count towards the limit
+    let options = Options { question_limit: Some(2), seed: Some(1), ..Options::default() };
+    let mut session = Session::new(files.clone(), options, None).unwrap();
+    let mut script = Script::lines(&["x", "1 is 1.", "2", "1"]);
+    session.run(&mut script).unwrap();
+    assert_eq!(asked(&script), ["1", "2", "1"]);
+    assert!(script.finished());
+
+    let options = Options { time_limit: Some(std::time::Duration::ZERO), ..Options::default() };
+    let mut session = Session::new(files.clone(), options, None).unwrap();
+    let mut script = Script::lines(&[]);
+    session.run(&mut script).unwrap();
+    assert_eq!(session.summary().answers(), 0);
+
+    assert!(Session::new(files.clone(), Options { due_only: true, ..Options::default() }, None).is_err());
+
+    let path = std::env::temp_dir().join(format!("quickstudy-limits-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let study = |options: Options, answers: &[&str]| {
+        let mut session = Session::new(files.clone(), options, Some(Progress::open(&path).unwrap())).unwrap();
+        let mut script = Script::lines(answers);
+        session.run(&mut script).unwrap();
+        assert!(script.finished());
+        asked(&script)
+    };
+
+    // Only one new question a day, but questions that have been seen before are still asked
+    let new_per_day = || Options { new_per_day: Some(1), ..Options::default() };
+    assert_eq!(study(new_per_day(), &["1"]), ["1"]);
+    assert_eq!(study(new_per_day(), &["1"]), ["1"]);
+    // `1` isn't due again until tomorrow, and the rest are new
+    assert_eq!(study(Options { srs: true, due_only: true, ..Options::default() }, &[]), Vec::<String>::new());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn seeded_shuffles_are_reproducible() {
+    use crate::random::Rng;
+
+    let shuffled = |seed| {
+        let mut items = (0..20).collect::<Vec<_>>();
+        Rng::new(seed).shuffle(&mut items);
+        items
+    };
+
+    assert_eq!(shuffled(42), shuffled(42));
+    assert_ne!(shuffled(42), shuffled(43));
+
+    let mut sorted = shuffled(42);
+    sorted.sort();
+    assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+}
+
+#[test]
+fn shuffled_choices_grade_the_same() {
+    use crate::random::Rng;
+
+    let mut question = Parser::new("[a | b | c | d] {1} {1}; w, x, y, z", "<test>").next().unwrap().unwrap();
+    question.shuffle_choices(&mut Rng::new(7));
+
+    let answers = ["b", "z", "w"].iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert!(question.check_answers(answers).is_none());
+
+    let original = Parser::new("[a | b | c | d] {1} {1}; w, x, y, z", "<test>").next().unwrap().unwrap();
+    assert_ne!(question, original);
+}
+
+#[test]
+fn counts_questions_introduced_recently() {
+    use crate::progress::*;
+
+    let path = std::env::temp_dir().join(format!("quickstudy-introduced-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let attempt = |question: &str, time| Attempt {
+        question: question.to_string(),
+        file: "f".to_string(),
+        session: time,
+        time,
+        correct: true,
+        score: 1.0,
+        answers: Vec::new(),
+        rating: None,
+        retry: 0,
+        leitner: None,
+    };
+
+    let mut progress = Progress::open(&path).unwrap();
+    progress.record(attempt("old", 100)).unwrap();
+    progress.record(attempt("old", 1000)).unwrap();
+    progress.record(attempt("new", 900)).unwrap();
+    progress.record(attempt("newer", 950)).unwrap();
+
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(progress.introduced_since(900), 2);
+    assert_eq!(progress.introduced_since(0), 3);
+}
+
+#[test]
+fn exam_report_lists_wrong_answers() {
+    use crate::report::*;
+
+    let mut report = ExamReport::new();
+
+    for (src, answers) in [("The sky is [blue]", vec!["blue"]), ("[Paris] is in {1}; France", vec!["Paris", "Spain"]), ("[a | b] and {1}; e, f", vec!["c", "e"])] {
+        let question = Parser::new(src, "<test>").next().unwrap().unwrap();
+        let given = answers.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        report.push(Answered {
+            prompt: question.prompt(),
+            given: given.clone(),
+            expected: question.expected(),
+            correct: question.check_answers(given).is_none(),
+        });
+    }
+
+    assert_eq!(
+        report.to_string(),
+        "Score: 1/3 (33%)\n\nWrong answers:\n1. ___ is in ___\n   Given:    Paris, Spain\n   Expected: Paris, France\n\
+         2. ___ and ___\n   Given:    c, e\n   Expected: a OR b, e OR f\n"
+    );
+}
+
+#[test]
+fn summary_counts_retries_and_hardest() {
+    use crate::summary::*;
+    use std::time::Duration;
+
+    let mut summary = Summary::new();
+    summary.record("[a]", "___", false, Duration::from_secs(4));
+    summary.record("[b]", "b ___", true, Duration::from_secs(2));
+    summary.record("[a]", "___", false, Duration::from_secs(3));
+    summary.record("[a]", "___", true, Duration::from_secs(3));
+    summary.set_elapsed(Duration::from_secs(75));
+
+    assert_eq!(
+        summary.to_string(),
+        "Questions asked:       2 (2 retries)\n\
+         Accuracy:              50% (2/4)\n\
+         Average response time: 3.0s\n\
+         Time spent:            1m 15s\n\
+         Retried questions:     1\n\
+         Hardest questions:\n  - ___ (missed 2 time(s))\n"
+    );
+
+    let json = summary.to_json();
+    assert_eq!(json["accuracy"], serde_json::json!(0.5));
+    assert_eq!(json["average_first_key_seconds"], serde_json::Value::Null);
+
+    summary.record_first_keys([Duration::from_secs(1), Duration::from_secs(2)]);
+    assert_eq!(summary.to_json()["average_first_key_seconds"], serde_json::json!(1.5));
+    assert_eq!(json["hardest"], serde_json::json!([{ "question": "[a]", "missed": 2 }]));
+}
+
+#[test]
+fn outlines_sections_and_tags() {
+    use crate::stats::*;
+
+    let src = "[a]\n## Verbs\n# tags: irregular, common\n[b]\n# just a comment\n[c]\n## Nouns\n[d]";
+
+    let placement = |section: Option<&str>, tags: &[&str]| Placement {
+        section: section.map(ToString::to_string),
+        tags: tags.iter().map(ToString::to_string).collect(),
+    };
+
+    assert_eq!(
+        outline(src, "<test>"),
+        vec![
+            ("[a]".to_string(), placement(None, &[])),
+            ("[b]".to_string(), placement(Some("Verbs"), &["irregular", "common"])),
+            ("[c]".to_string(), placement(Some("Verbs"), &["irregular", "common"])),
+            ("[d]".to_string(), placement(Some("Nouns"), &[])),
+        ]
+    );
+}
+
+#[test]
+fn stats_accuracy_forecast_and_streaks() {
+    use crate::{progress::*, stats::*};
+    use std::collections::HashMap;
+
+    let now = 100 * DAY + 3600;
+    let attempt = |question: &str, day: u64, correct, retry| Attempt {
+        question: question.to_string(),
+        file: "deck.txt".to_string(),
+        session: day * DAY,
+        time: day * DAY,
+        correct,
+        score: if correct { 1.0 } else { 0.0 },
+        answers: Vec::new(),
+        rating: None,
+        retry,
+        leitner: None,
+    };
+
+    let a = [attempt("[a]", 90, true, 0), attempt("[a]", 99, false, 0), attempt("[a]", 99, true, 1)];
+    let b = [attempt("[b]", 98, true, 0), attempt("[b]", 100, true, 0)];
+
+    let mut placements = HashMap::new();
+    placements.insert("[b]".to_string(), Placement { section: Some("Easy".to_string()), tags: vec!["x".to_string()] });
+
+    let stats = Stats::new(vec![("[a]", &a[..]), ("[b]", &b[..])].into_iter(), &placements, now);
+
+    assert_eq!(stats.overall.attempts, 4);
+    assert_eq!(stats.overall.accuracy(), Some(0.75));
+    assert_eq!(stats.overall.retention(), Some(0.5));
+    assert_eq!(stats.files["deck.txt"].week, (3, 2));
+    assert_eq!(stats.sections["Easy"].attempts, 2);
+    assert_eq!(stats.tags["x"].accuracy(), Some(1.0));
+
+    // `[a]` was missed yesterday so it's due today, and `[b]` was seen twice so it's due in 6 days
+    assert_eq!(stats.forecast, vec![1, 0, 0, 0, 0, 0, 1]);
+
+    assert_eq!((stats.current_streak, stats.longest_streak), (3, 3));
+    // Studying on the very first day there is doesn't underflow
+    let first = [attempt("[a]", 0, true, 0), attempt("[a]", 1, true, 0)];
+    assert_eq!(Stats::new(vec![("[a]", &first[..])].into_iter(), &placements, DAY).longest_streak, 2);
+    assert!(stats.to_csv().starts_with("kind,name,attempts,accuracy,accuracy_7d,accuracy_30d,retention\noverall,overall,4,0.750,"));
+}
+
+#[test]
+fn slowest_blank_falls_back_to_average() {
+    use crate::render::{Response, Timing};
+    use std::time::Duration;
+
+    let timing = |first_key, total| Timing { first_key: Duration::from_secs(first_key), total: Duration::from_secs(total) };
+
+    let timed = Response {
+        answers: vec!["a".to_string(), "b".to_string()],
+        timings: vec![timing(1, 3), timing(4, 8)],
+        total: Duration::from_secs(11),
+    };
+    assert_eq!(timed.slowest_blank(), Duration::from_secs(8));
+
+    let untimed = Response { timings: Vec::new(), total: Duration::from_secs(10), ..timed };
+    assert_eq!(untimed.slowest_blank(), Duration::from_secs(5));
+}
+
+#[test]
+fn input_edits_blanks_and_times_them() {
+    use crate::input;
+    use crate::render::{script::Script, BlankKind, BlankView, Event, Part, QuestionView};
+
+    let question = Parser::new("[Paris] is the capital of {1}.; France", "").next().unwrap().unwrap().view();
+    assert_eq!(
+        question.parts,
+        vec![
+            Part::Blank(BlankView { kind: BlankKind::FreeText }),
+            Part::Text(" is the capital of ".to_string()),
+            Part::Blank(BlankView { kind: BlankKind::Pool(0) }),
+            Part::Text(".".to_string()),
+        ]
+    );
+    let kinds = Parser::new("[a], [b | c], {1}; d", "").next().unwrap().unwrap().view().blanks().map(|blank| blank.kind).collect::<Vec<_>>();
+    assert_eq!(kinds, [BlankKind::FreeText, BlankKind::OneOf, BlankKind::Pool(0)]);
+
+    let typed = |s: &str| s.chars().map(Event::Char).collect::<Vec<_>>();
+    let mut events = typed("Pars");
+    events.extend([Event::Left, Event::Char('i'), Event::NextBlank]);
+    events.extend(typed("XFrnce"));
+    events.extend([Event::Home, Event::Delete, Event::Right, Event::Right, Event::Char('a'), Event::Enter]);
+
+    let mut script = Script::new(events);
+    let response = input::ask(&mut script, &question).unwrap().unwrap();
+    assert_eq!(response.answers, vec!["Paris".to_string(), "France".to_string()]);
+    assert_eq!(response.timings.len(), 2);
+    assert!(script.finished());
+
+    // Backspacing out of an empty blank goes back into the one before it, and all the answers can come in at once
+    let mut script = Script::new([Event::Backspace, Event::Backspace, Event::Answers(vec!["Rome".to_string(), "Italy".to_string()])]);
+    let response = input::ask(&mut script, &QuestionView { parts: question.parts.clone(), pools: Vec::new() }).unwrap().unwrap();
+    assert_eq!(response.answers, vec!["Rome".to_string(), "Italy".to_string()]);
+    assert!(response.timings.is_empty());
+
+    let mut script = Script::new([Event::Line("a".to_string()), Event::Line("4".to_string()), Event::Char('1')]);
+    assert!(input::type_out(&mut script, "A").unwrap());
+    assert_eq!(input::rate_recall(&mut script).unwrap(), Some(crate::schedule::Rating::Hard));
+}
+
+#[test]
+fn scripted_session_retries_and_corrects() {
+    use crate::render::script::Script;
+    use crate::session::{Options, Session};
+
+    let src = "## Capitals\n[Paris] is the capital of [France].\nThe sky is [blue].\nBroken [line\n";
+    let files = vec![("capitals.txt".to_string(), src.to_string())];
+
+    let mut session = Session::new(files, Options { seed: Some(1), ..Options::default() }, None).unwrap();
+    let mut script = Script::lines(&[
+        "Paris", "Spain", "paris is the capital of spain", "PARIS IS THE CAPITAL OF FRANCE.",
+        "blue",
+        "Paris", "France",
+    ]);
+    session.run(&mut script).unwrap();
+
+    let (summary, shown) = script.shown.split_last().unwrap();
+    assert_eq!(shown, [
+        "Status: capitals.txt › Capitals, question 1/2, score 0/0, retrying 0",
+        "Ask: [___] is the capital of ___.",
+        "Ask: Paris is the capital of [___].",
+        "Answered: Paris is the capital of Spain.",
+        "Incorrect: Paris is the capital of France.",
+        "Type out: Paris is the capital of France.",
+        // Typing it out wrong has it typed out again
+        "Type out: Paris is the capital of France.",
+        "Status: capitals.txt › Capitals, question 2/2, score 0/1, retrying 1",
+        "Ask: The sky is [___].",
+        "Answered: The sky is blue.",
+        "Correct",
+        "Error: capitals.txt:4:8 Unexpected end of answer!",
+        "Status: capitals.txt › Capitals, question 2/2, score 1/2, retrying 0",
+        "Ask: [___] is the capital of ___.",
+        "Ask: Paris is the capital of [___].",
+        "Answered: Paris is the capital of France.",
+        "Correct",
+    ]);
+    assert!(summary.starts_with("Summary: Questions asked:       2 (1 retries)\nAccuracy:              66% (2/3)\n"));
+    assert!(script.finished());
+    assert_eq!(session.summary().answers(), 3);
+}
+
+#[test]
+fn missed_questions_come_back_later_and_at_the_end() {
+    use crate::render::script::Script;
+    use crate::session::{Options, Session};
+
+    let src = (1..=6).map(|n| format!("{n} is [{n}].\n")).collect::<String>();
+    let files = vec![("numbers.txt".to_string(), src)];
+    let options = Options { retry_gap: 2, max_retries: 3, seed: Some(1), ..Options::default() };
+
+    let mut session = Session::new(files, options, None).unwrap();
+    // Every wrong answer is followed by typing out the correction
+    let mut script = Script::lines(&[
+        "x", "1 is 1.", "2", "3",
+        "x", "1 is 1.",
+        "x", "4 is 4.", "5", "6",
+        "4",
+        "x", "1 is 1.",
+        "x", "1 is 1.",
+    ]);
+    session.run(&mut script).unwrap();
+
+    let asked = script.shown.iter().filter_map(|shown| shown.strip_prefix("Answered: ")).map(|q| &q[..1]).collect::<Vec<_>>();
+    // A first miss comes back 2 questions later, and after that at the end. `4` is only retried once since the retry
+    // was right, and `1` stops after 3 retries.
+    assert_eq!(asked, ["1", "2", "3", "1", "4", "5", "6", "4", "1", "1"]);
+    assert!(script.finished());
+}
+
+#[test]
+fn questions_without_blanks_are_not_asked() {
+    use crate::render::script::Script;
+    use crate::session::{Options, Session};
+
+    let files = vec![("f.txt".to_string(), "Just some text\nOne is [1].\n".to_string())];
+    let mut session = Session::new(files, Options::default(), None).unwrap();
+    let mut script = Script::lines(&["1"]);
+    session.run(&mut script).unwrap();
+
+    assert!(script.finished());
+    assert_eq!(script.shown[..3], ["Error: f.txt:1 Question has no blanks to fill in!", "Status: f.txt, question 1/1, score 0/0, retrying 0", "Ask: One is [___]."]);
+    assert_eq!((session.summary().correct(), session.summary().answers()), (1, 1));
+}
+
+#[test]
+fn quitting_still_reports_what_was_answered() {
+    use crate::render::{script::Script, Event};
+    use crate::session::{Options, Session};
+
+    let files = vec![("f.txt".to_string(), "One is [1].\nTwo is [2].\nThree is [3].\n".to_string())];
+
+    let mut session = Session::new(files.clone(), Options { exam: true, ..Options::default() }, None).unwrap();
+    let mut script = Script::new([Event::Line("1".to_string()), Event::Char('x'), Event::Quit]);
+    session.run(&mut script).unwrap();
+    assert!(script.finished());
+    assert_eq!(session.report().score(), 1);
+    assert!(script.shown.iter().any(|shown| shown == "Report: Score: 1/1 (100%)\n"));
+    assert!(script.shown.last().unwrap().starts_with("Summary: Questions asked:       1 "));
+
+    // Stopping while typing out a correction still counts the answer
+    let mut session = Session::new(files, Options::default(), None).unwrap();
+    let mut script = Script::new([Event::Line("2".to_string()), Event::Quit]);
+    session.run(&mut script).unwrap();
+    assert!(script.finished());
+    assert_eq!((session.summary().correct(), session.summary().answers()), (0, 1));
+    assert!(script.shown.last().unwrap().starts_with("Summary: "));
+}
+
+#[test]
+fn scripted_exam_only_reports_at_the_end() {
+    use crate::render::script::Script;
+    use crate::session::{Options, Session};
+
+    let src = "[Paris] is the capital of France.\nThe sky is [blue].\nGrass is [green].\n";
+    let files = vec![("exam.txt".to_string(), src.to_string())];
+    let options = Options { exam: true, question_limit: Some(2), seed: Some(1), ..Options::default() };
+
+    let mut session = Session::new(files, options, None).unwrap();
+    let mut script = Script::lines(&["Rome", "blue"]);
+    session.run(&mut script).unwrap();
+
+    assert!(!script.shown.iter().any(|shown| shown.starts_with("Correct") || shown.starts_with("Incorrect")));
+    assert_eq!(script.shown[1..3], ["Ask: [___] is the capital of France.", "Answered: Rome is the capital of France."]);
+    // Nothing about the score is shown until the report
+    assert_eq!(script.shown[3], "Status: exam.txt, question 2/2, retrying 0");
+
+    let report = script.shown.iter().find(|shown| shown.starts_with("Report: ")).unwrap();
+    assert_eq!(report, &format!("Report: {}", session.report()));
+    assert!(report.contains("Score: 1/2 (50%)"));
+    assert!(report.contains("Given:    Rome\n   Expected: Paris\n"));
+    assert!(script.shown.last().unwrap().starts_with("Summary: "));
+}
+
+#[cfg(any(feature = "tui", feature = "crossterm-tui"))]
+#[test]
+fn tui_wraps_and_fills_in_blanks() {
+    use crate::render::screen::{fill_in, paint_grades, wrap};
+    use crate::theme::Paint;
+
+    let span = |paint, text: &str| (paint, text.to_string());
+
+    assert_eq!(
+        wrap(&[span(Paint::Plain, "abcd"), span(Paint::Blank, "efg\n\nhi")], 3),
+        vec![
+            vec![span(Paint::Plain, "abc")],
+            vec![span(Paint::Plain, "d"), span(Paint::Blank, "ef")],
+            vec![span(Paint::Blank, "g")],
+            vec![],
+            vec![span(Paint::Blank, "hi")],
+        ]
+    );
+
+    let question = Parser::new("The [sky] is [blue].", "").next().unwrap().unwrap().view();
+    let answers = ["sky".to_string(), String::new()];
+    assert_eq!(
+        fill_in(&question, &answers, Some((0, 1))),
+        (
+            vec![span(Paint::Plain, "The "), span(Paint::Active, "sky"), span(Paint::Plain, " is "), span(Paint::Blank, "___"), span(Paint::Plain, ".")],
+            Some(5)
+        )
+    );
+    assert_eq!(
+        fill_in(&question, &answers, Some((1, 0))),
+        (vec![span(Paint::Plain, "The "), span(Paint::Blank, "sky"), span(Paint::Plain, " is "), span(Paint::Plain, ".")], Some(11))
+    );
+
+    // Each blank is coloured by how it went, even right next to another one
+    let question = Parser::new("[a | b][c]!", "").next().unwrap().unwrap().view();
+    let (answered, _) = fill_in(&question, &["a".to_string(), "x".to_string()], None);
+    assert_eq!(paint_grades(&answered, &[true, false]), vec![span(Paint::Correct, "a"), span(Paint::Wrong, "x"), span(Paint::Plain, "!")]);
+}
+
+#[cfg(any(feature = "tui", feature = "crossterm-tui"))]
+#[test]
+fn parses_themes() {
+    use crate::theme::{Color, Paint, Style, Theme};
+
+    let theme = Theme::parse("# My colours\n\nwrong = magenta\nactive = white on blue\n").unwrap();
+    assert_eq!(theme.style(Paint::Wrong), Some(Style { fg: Color::Magenta, bg: Color::Default }));
+    assert_eq!(theme.style(Paint::Active), Some(Style { fg: Color::White, bg: Color::Blue }));
+    assert_eq!(theme.style(Paint::Correct), Theme::default().style(Paint::Correct));
+    assert_eq!(theme.style(Paint::Plain), None);
+
+    assert_eq!(Theme::parse("wrong = purple").unwrap_err(), "Line 1: Unknown colour `purple`!");
+    assert_eq!(Theme::parse("\nborder = red").unwrap_err(), "Line 2: Unknown part of the screen `border`!");
+    assert_eq!(Theme::parse("wrong red").unwrap_err(), "Line 1: Expected `=` in `wrong red`!");
+}
+
+#[cfg(any(feature = "tui", feature = "crossterm-tui"))]
+#[test]
+fn tui_status_line_fits_the_width() {
+    use crate::render::{screen::status_line, Status};
+    use std::time::{Duration, Instant};
+
+    let status = Status {
+        file: "verbs.txt".to_string(),
+        section: Some("Irregular".to_string()),
+        position: 3,
+        total: 20,
+        score: Some((1, 2)),
+        retries: 1,
+        started: Instant::now(),
+        time_limit: Some(Duration::from_secs(600)),
+    };
+    let elapsed = Duration::from_secs(65);
+
+    assert_eq!(
+        status_line(&status, elapsed, 80),
+        " verbs.txt › Irregular   Question 3/20  Score 1/2  Retrying 1  1:05 (8:55 left) "
+    );
+    assert_eq!(status_line(&status, elapsed, 60), " ver Question 3/20  Score 1/2  Retrying 1  1:05 (8:55 left) ");
+
+    let status = Status { section: None, retries: 0, time_limit: None, ..status };
+    assert_eq!(status_line(&status, elapsed, 40), " verbs.t Question 3/20  Score 1/2  1:05 ");
+
+    // Exams don't give away how it's going
+    let status = Status { score: None, ..status };
+    assert_eq!(status_line(&status, elapsed, 40), " verbs.txt          Question 3/20  1:05 ");
+}
+
+#[cfg(not(any(feature = "tui", feature = "crossterm-tui")))]
+#[test]
+fn cli_prompts_one_blank_at_a_time() {
+    use crate::render::{cli::{line_event, prompt_text}, Cursor, Event, Prompt};
+
+    let question = Parser::new("[Washington, D.C.] is the capital of the [USA].", "").next().unwrap().unwrap().view();
+    let answers = vec!["Washington, D.C.".to_string(), String::new()];
+    let prompt = Prompt::Blanks { question: &question, answers: &answers, cursor: Cursor { blank: 1, at: 0 } };
+
+    assert_eq!(prompt_text(&prompt, false), "Washington, D.C. is the capital of the [___].: ");
+    // A comma is only a separator when every blank is answered on one line
+    assert_eq!(line_event("Washington, D.C.\n", true, false), Event::Line("Washington, D.C.".to_string()));
+
+    let answers = vec![String::new(), String::new()];
+    let prompt = Prompt::Blanks { question: &question, answers: &answers, cursor: Cursor { blank: 0, at: 0 } };
+    assert_eq!(prompt_text(&prompt, true), "___ is the capital of the ___.: ");
+    assert_eq!(line_event("Paris,France\n", true, true), Event::Answers(vec!["Paris".to_string(), "France".to_string()]));
+    // Corrections are typed out whole, commas and all
+    assert_eq!(line_event("Washington, D.C.\n", false, true), Event::Line("Washington, D.C.".to_string()));
+    assert_eq!(prompt_text(&Prompt::Correction { correction: "Washington, D.C.", typed: "" }, true), "Please type that out: ");
+}
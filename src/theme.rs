@@ -0,0 +1,155 @@
+//! Colours for the full screen renderer, read from `$XDG_CONFIG_HOME/quickstudy/theme`.
+//!
+//! Each line of the file colours one part of the screen, like `wrong = red` or `active = black on yellow`, and lines
+//! starting with `#` are comments. Anything the file doesn't mention keeps its default colour.
+
+use std::{env, fs, io, path::{Path, PathBuf}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    /// Whatever the terminal uses when nothing else is asked for.
+    Default,
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+}
+
+impl Color {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "default" => Some(Color::Default),
+            "black" => Some(Color::Black),
+            "red" => Some(Color::Red),
+            "green" => Some(Color::Green),
+            "yellow" => Some(Color::Yellow),
+            "blue" => Some(Color::Blue),
+            "magenta" => Some(Color::Magenta),
+            "cyan" => Some(Color::Cyan),
+            "white" => Some(Color::White),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Style {
+    const fn new(fg: Color, bg: Color) -> Self {
+        Self { fg, bg }
+    }
+}
+
+/// The parts of the screen that can be coloured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Paint {
+    /// Anything that isn't one of the others, which is always left as it is.
+    Plain,
+    /// A blank, whether it's been answered or not.
+    Blank,
+    /// The blank that's being typed into.
+    Active,
+    /// Answers that turned out to be right.
+    Correct,
+    /// Answers that turned out to be wrong, and the correction.
+    Wrong,
+    Error,
+}
+
+impl Paint {
+    /// Everything but `Plain`, in the order their styles are kept in.
+    pub const STYLED: [Paint; 5] = [Paint::Blank, Paint::Active, Paint::Correct, Paint::Wrong, Paint::Error];
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "blank" => Some(Paint::Blank),
+            "active" => Some(Paint::Active),
+            "correct" => Some(Paint::Correct),
+            "wrong" => Some(Paint::Wrong),
+            "error" => Some(Paint::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    styles: [Style; 5],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            styles: [
+                Style::new(Color::Cyan, Color::Default),
+                Style::new(Color::Black, Color::Cyan),
+                Style::new(Color::Green, Color::Default),
+                Style::new(Color::Red, Color::Default),
+                Style::new(Color::White, Color::Red),
+            ],
+        }
+    }
+}
+
+impl Theme {
+    /// How `paint` should look, or `None` for plain text.
+    pub fn style(&self, paint: Paint) -> Option<Style> {
+        let idx = Paint::STYLED.iter().position(|p| *p == paint)?;
+        Some(self.styles[idx])
+    }
+
+    /// Reads a theme from `src`, starting from the default one.
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut theme = Theme::default();
+
+        for (idx, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let err = |msg: String| format!("Line {}: {msg}", idx + 1);
+
+            let (name, colors) = line.split_once('=').ok_or_else(|| err(format!("Expected `=` in `{line}`!")))?;
+            let paint = Paint::parse(name.trim()).ok_or_else(|| err(format!("Unknown part of the screen `{}`!", name.trim())))?;
+
+            let color = |s: &str| Color::parse(s.trim()).ok_or_else(|| err(format!("Unknown colour `{}`!", s.trim())));
+            let style = match colors.split_once(" on ") {
+                Some((fg, bg)) => Style::new(color(fg)?, color(bg)?),
+                None => Style::new(color(colors)?, Color::Default),
+            };
+
+            let idx = Paint::STYLED.iter().position(|p| *p == paint).expect("Only styled paints can be parsed");
+            theme.styles[idx] = style;
+        }
+
+        Ok(theme)
+    }
+
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+        Some(config_dir.join("quickstudy").join("theme"))
+    }
+
+    /// Reads the theme at `path`, or gives the default one if there's no file there.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let display = path.display();
+
+        match fs::read_to_string(path) {
+            Ok(src) => Self::parse(&src).map_err(|e| format!("Could not read theme `{display}`: {e}")),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Could not read theme `{display}`: {e}!")),
+        }
+    }
+}